@@ -1,5 +1,326 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE_NAME: &str = ".phew.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid config {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommentStyle {
+    /// `<!-- text -->` with one space inside each delimiter.
+    Normalize,
+    /// Keep the spacing inside `<!--` and `-->` as written.
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConcatSpacing {
+    /// `$a . 'x'`
+    Spaced,
+    /// `$a.'x'`
+    Tight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EchoStyle {
+    /// `<?= $x ?>`
+    Short,
+    /// `<?php echo $x; ?>`
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonStyle {
+    Pretty,
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainWrap {
+    /// Every call of a split chain on its own line.
+    All,
+    /// As many calls per line as fit the line limit.
+    Fill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClassSort {
+    /// Keep the written order.
+    None,
+    Alphabetical,
+    /// Order by the first matching entry of `class_groups`.
+    Groups,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProseWrap {
+    /// Reflow text to fill lines up to the line limit.
+    Always,
+    /// Join each run of text onto a single line.
+    Never,
+    /// Keep text lines as written.
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuoteStyle {
+    /// Keep string quotes as written.
+    Preserve,
+    /// `"text"` becomes `'text'` when it has no `$`, backslash or `'`.
+    Single,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnclosedTags {
+    /// Print the missing end tag and report it.
+    Insert,
+    /// Print the element without an end tag, as in the source.
+    Preserve,
+    /// Refuse to format the file.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoidStyle {
+    /// `<br />`
+    SelfClosing,
+    /// `<br>`
+    Bare,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapAttributes {
+    /// One attribute per line only when the tag exceeds the line limit.
+    Auto,
+    /// One attribute per line whenever the tag has more than `wrap_attributes_threshold` attributes.
+    ForceExpandMultiline,
+    /// Keep the line breaks between attributes as written.
+    Preserve,
+    /// First attribute on the tag line, the rest aligned under it.
+    Aligned,
+}
+
+/// Formatter options loaded from `.phew.toml`; every field is optional in the file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Reformat the contents of `<style>` blocks as CSS.
+    pub format_css: bool,
+    /// Normalize declarations inside `style="..."` attributes.
+    pub format_style_attribute: bool,
+    /// Reindent the contents of JavaScript `<script>` blocks.
+    pub format_js: bool,
+    /// Reprint `application/json` and `application/ld+json` script blocks.
+    pub format_json: bool,
+    /// Format `<script>` blocks with HTML template types, e.g. `text/x-template`, as markup.
+    pub format_templates: bool,
+    /// Layout for JSON script blocks.
+    pub json_style: JsonStyle,
+    /// Attribute names whose values are compacted as JSON, e.g. `data-options`.
+    pub json_attributes: Vec<String>,
+    /// How attributes of a long tag are wrapped.
+    pub wrap_attributes: WrapAttributes,
+    /// Attribute count above which `force-expand-multiline` wraps a tag.
+    pub wrap_attributes_threshold: usize,
+    /// Keep `>` on the last attribute line of a wrapped tag instead of on its own line.
+    pub bracket_same_line: bool,
+    /// What to do with elements whose end tag is missing from the source.
+    pub unclosed_tags: UnclosedTags,
+    /// Blank lines kept between sibling nodes at any depth and between statements of a PHP block;
+    /// never at the start or end of a parent.
+    pub max_blank_lines: usize,
+    /// Spacing inside HTML comment delimiters; conditional comments are always kept as written.
+    pub comment_style: CommentStyle,
+    /// Reflow of text inside block elements; inline elements and `<?= ?>` echoes are never broken.
+    pub prose_wrap: ProseWrap,
+    /// Lowercase HTML tag and attribute names, keeping SVG/MathML camelCase names such as `viewBox`.
+    pub lowercase_names: bool,
+    /// Print boolean attributes bare: `disabled="disabled"` becomes `disabled`.
+    pub bare_boolean_attributes: bool,
+    /// Closing style of void elements.
+    pub void_style: VoidStyle,
+    /// Collapse whitespace and drop duplicates in `class` values, wrapping very long lists.
+    pub normalize_class: bool,
+    /// Order of classes inside `class` values.
+    pub class_sort: ClassSort,
+    /// Class groups for `class_sort = "groups"`, written like `attribute_groups`, e.g. `col-*`.
+    pub class_groups: Vec<String>,
+    /// Reorder attributes by `attribute_groups`, never across a PHP segment inside the tag.
+    pub sort_attributes: bool,
+    /// Attribute groups in output order: exact names, `|` alternatives and `*` prefixes;
+    /// a bare `*` places everything unmatched.
+    pub attribute_groups: Vec<String>,
+    /// Lowercase PHP keywords, `true`/`false`/`null`, built-in types and casts; identifiers and strings are kept.
+    pub lowercase_php_keywords: bool,
+    /// Rewrite `if`/`foreach`/`for`/`while` brace blocks that open and close in separate PHP blocks
+    /// around markup into the `if (...): ... endif;` syntax.
+    pub alternative_syntax: bool,
+    /// Rewrite long `array(...)` literals as `[...]`.
+    pub short_array_syntax: bool,
+    /// Quotes of PHP string literals; strings with interpolation or escapes keep double quotes.
+    pub php_quotes: QuoteStyle,
+    /// How single-statement `echo`/`print` blocks and `<?= ?>` echoes are printed.
+    pub echo_style: EchoStyle,
+    /// Method calls a too-long echoed chain needs before it is split at `->`.
+    pub chain_min_calls: usize,
+    /// Layout of the calls of a split chain.
+    pub chain_wrap: ChainWrap,
+    /// Keep the first call of a split chain on the receiver line, as in `$form->field(...)`.
+    pub chain_first_call_inline: bool,
+    /// Spacing around the `.` concatenation operator in PHP code.
+    pub concat_spacing: ConcatSpacing,
+    /// Move heredoc and nowdoc bodies with their closing marker to the indentation of the
+    /// surrounding code; needs `php_version` 7.3 or later.
+    pub reindent_heredocs: bool,
+    /// Lowest PHP version the views run on, as `"major.minor"`; gates output that older versions reject.
+    pub php_version: String,
+    /// Methods whose `<<<JS` / `<<<CSS` heredoc arguments are formatted, e.g. `registerJs`.
+    pub heredoc_methods: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            format_css: true,
+            format_style_attribute: false,
+            format_js: true,
+            format_json: true,
+            format_templates: true,
+            json_style: JsonStyle::Pretty,
+            json_attributes: Vec::new(),
+            wrap_attributes: WrapAttributes::Auto,
+            wrap_attributes_threshold: 1,
+            bracket_same_line: false,
+            unclosed_tags: UnclosedTags::Insert,
+            max_blank_lines: 1,
+            comment_style: CommentStyle::Normalize,
+            prose_wrap: ProseWrap::Preserve,
+            lowercase_names: false,
+            bare_boolean_attributes: false,
+            void_style: VoidStyle::SelfClosing,
+            normalize_class: true,
+            class_sort: ClassSort::None,
+            class_groups: Vec::new(),
+            sort_attributes: false,
+            attribute_groups: [
+                "id", "class", "name", "type", "value", "href|src", "data-*", "aria-*", "hx-*", "on*", "*",
+            ]
+            .map(String::from)
+            .to_vec(),
+            lowercase_php_keywords: true,
+            alternative_syntax: false,
+            short_array_syntax: false,
+            php_quotes: QuoteStyle::Preserve,
+            echo_style: EchoStyle::Short,
+            chain_min_calls: 2,
+            chain_wrap: ChainWrap::All,
+            chain_first_call_inline: true,
+            concat_spacing: ConcatSpacing::Spaced,
+            reindent_heredocs: false,
+            php_version: "7.3".into(),
+            heredoc_methods: vec!["registerJs".into(), "registerCss".into()],
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&source).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Reports whether `php_version` is at least `major.minor`; an unreadable version never is.
+    pub fn targets_php(&self, major: u32, minor: u32) -> bool {
+        let mut parts = self.php_version.trim().split('.').map(str::parse::<u32>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(target_major)), None) => (target_major, 0) >= (major, minor),
+            (Some(Ok(target_major)), Some(Ok(target_minor))) => (target_major, target_minor) >= (major, minor),
+            _ => false,
+        }
+    }
+
+    /// Loads the nearest `.phew.toml` found in `start` or its ancestors, falling back to defaults.
+    pub fn discover(start: &Path) -> Result<Self, ConfigError> {
+        match find_config_file(start) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+fn find_config_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn empty_source_gives_defaults() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn overrides_single_option() {
+        let config = Config::from_toml("format_style_attribute = true").unwrap();
+        assert!(config.format_style_attribute);
+        assert!(config.format_css);
+    }
+
+    #[test]
+    fn parses_kebab_case_enum_and_list() {
+        let config = Config::from_toml("json_style = \"compact\"\njson_attributes = [\"data-options\"]").unwrap();
+        assert_eq!(config.json_style, JsonStyle::Compact);
+        assert_eq!(config.json_attributes, vec!["data-options".to_string()]);
+    }
+
+    #[test]
+    fn rejects_unknown_option() {
+        assert!(Config::from_toml("unknown_option = 1").is_err());
+    }
+
     #[test]
-    fn it_loads() {}
+    fn php_version_compared_by_major_and_minor() {
+        let config = |version: &str| Config {
+            php_version: version.into(),
+            ..Config::default()
+        };
+        assert!(config("7.3").targets_php(7, 3));
+        assert!(config("8").targets_php(7, 3));
+        assert!(config("8.1.2").targets_php(8, 1));
+        assert!(!config("7.2").targets_php(7, 3));
+        assert!(!config("latest").targets_php(5, 4));
+    }
 }
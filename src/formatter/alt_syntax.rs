@@ -0,0 +1,282 @@
+use super::array::heredoc_end;
+use super::php::{comment_end, split_trailing_comment};
+use super::split::find_matching_close;
+use crate::parser::ast::Node;
+
+/// Control structures with an alternative syntax, as `(keyword, end keyword)`.
+const STRUCTURES: &[(&str, &str)] = &[
+    ("if", "endif"),
+    ("foreach", "endforeach"),
+    ("for", "endfor"),
+    ("while", "endwhile"),
+];
+
+#[derive(Debug, PartialEq)]
+enum Brace {
+    /// `if (...) {`, with the keyword index in `STRUCTURES` and the header to print.
+    Open(usize, String),
+    /// `} else {` or `} elseif (...) {`, with the header to print.
+    Branch(String),
+    /// `}`
+    Close,
+}
+
+/// Parses `keyword (condition)` at the start of `code`, returning the keyword and the condition
+/// with its parentheses when nothing follows it.
+fn header(code: &str) -> Option<(&str, &str)> {
+    let keyword_end = code.find(|c: char| !c.is_alphabetic())?;
+    let (keyword, rest) = code.split_at(keyword_end);
+    let condition = rest.trim_start();
+    let chars: Vec<char> = condition.chars().collect();
+    if chars.first() != Some(&'(') || find_matching_close(&chars, 0)? != chars.len() - 1 {
+        return None;
+    }
+    Some((keyword, condition))
+}
+
+fn classify(code: &str) -> Option<Brace> {
+    let code = code.trim();
+    if code == "}" {
+        return Some(Brace::Close);
+    }
+    let head = code.strip_suffix('{')?.trim_end();
+    if let Some(branch) = head.strip_prefix('}') {
+        let branch = branch.trim_start();
+        branch.get(..4).filter(|word| word.eq_ignore_ascii_case("else"))?;
+        let rest = branch[4..].trim_start();
+        if rest.is_empty() {
+            return Some(Brace::Branch("else:".into()));
+        }
+        let (keyword, condition) = header(rest)?;
+        return keyword
+            .eq_ignore_ascii_case("if")
+            .then(|| Brace::Branch(format!("elseif {condition}:")));
+    }
+    let (keyword, condition) = header(head)?;
+    let kind = STRUCTURES
+        .iter()
+        .position(|(name, _)| name.eq_ignore_ascii_case(keyword))?;
+    Some(Brace::Open(kind, format!("{} {condition}:", STRUCTURES[kind].0)))
+}
+
+/// The `{` and `}` of PHP code, outside strings, comments and heredocs.
+fn braces(code: &str) -> Vec<char> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(end) = comment_end(&chars, i).or_else(|| heredoc_end(&chars, i)) {
+            i = end;
+            continue;
+        }
+        match chars[i] {
+            '\'' | '"' => {
+                let quote = chars[i];
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            '{' | '}' => found.push(chars[i]),
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// A brace block left open in the sibling list.
+struct Frame {
+    /// Index in `STRUCTURES`, or `None` for braces of other code and for a structure given up on.
+    kind: Option<usize>,
+    /// Node index and rewritten code of the header and each branch.
+    parts: Vec<(usize, String)>,
+    after_else: bool,
+}
+
+impl Frame {
+    fn other() -> Self {
+        Self {
+            kind: None,
+            parts: Vec::new(),
+            after_else: false,
+        }
+    }
+}
+
+/// Rewritten code for each PHP block of one sibling list. A structure is left as written when it
+/// is not closed, is closed or continued by other code, has a second `else` or crosses an
+/// element boundary; blocks whose braces balance on their own, like closures, do not matter.
+fn rewrites(nodes: &[Node]) -> Vec<Option<String>> {
+    let mut rewritten = vec![None; nodes.len()];
+    let mut open: Vec<Frame> = Vec::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let Node::PhpBlock(code) = node else {
+            continue;
+        };
+        let (code, comment) = split_trailing_comment(code);
+        let with_comment = |text: String| {
+            if comment.is_empty() {
+                text
+            } else {
+                format!("{text} {comment}")
+            }
+        };
+        match classify(code) {
+            Some(Brace::Open(kind, header)) => open.push(Frame {
+                kind: Some(kind),
+                parts: vec![(index, with_comment(header))],
+                after_else: false,
+            }),
+            Some(Brace::Branch(header)) => {
+                if let Some(frame) = open.last_mut() {
+                    if frame.kind.is_some_and(|kind| STRUCTURES[kind].0 == "if") && !frame.after_else {
+                        frame.after_else = header == "else:";
+                        frame.parts.push((index, with_comment(header)));
+                    } else {
+                        frame.kind = None;
+                    }
+                }
+            }
+            Some(Brace::Close) => {
+                if let Some(Frame {
+                    kind: Some(kind),
+                    parts,
+                    ..
+                }) = open.pop()
+                {
+                    for (part, header) in parts {
+                        rewritten[part] = Some(header);
+                    }
+                    rewritten[index] = Some(with_comment(format!("{};", STRUCTURES[kind].1)));
+                }
+            }
+            None => {
+                for brace in braces(code) {
+                    if brace == '{' {
+                        open.push(Frame::other());
+                    } else {
+                        open.pop();
+                    }
+                }
+            }
+        }
+    }
+    rewritten
+}
+
+/// Rewrites brace-style control structures whose braces open and close in separate PHP blocks
+/// of the same parent into the alternative `if (...): ... endif;` syntax.
+pub fn alternative_syntax(nodes: &[Node]) -> Vec<Node> {
+    let rewritten = rewrites(nodes);
+    nodes
+        .iter()
+        .zip(rewritten)
+        .map(|(node, code)| match (node, code) {
+            (_, Some(code)) => Node::PhpBlock(code),
+            (
+                Node::Element {
+                    name,
+                    attributes,
+                    children,
+                    closed,
+                },
+                None,
+            ) => Node::Element {
+                name: name.clone(),
+                attributes: attributes.clone(),
+                children: alternative_syntax(children),
+                closed: *closed,
+            },
+            (node, None) => node.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ast, lexer};
+    use pretty_assertions::assert_eq;
+
+    fn php_blocks(input: &str) -> Vec<String> {
+        alternative_syntax(&ast::parse(lexer::tokenize(input)))
+            .into_iter()
+            .filter_map(|node| match node {
+                Node::PhpBlock(code) => Some(code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn headers_classified() {
+        assert_eq!(classify(" if ($a) { "), Some(Brace::Open(0, "if ($a):".into())));
+        assert_eq!(
+            classify("FOREACH ($items as $item) {"),
+            Some(Brace::Open(1, "foreach ($items as $item):".into()))
+        );
+        assert_eq!(classify("} else if ($b) {"), Some(Brace::Branch("elseif ($b):".into())));
+        assert_eq!(classify("}else{"), Some(Brace::Branch("else:".into())));
+        assert_eq!(classify("}"), Some(Brace::Close));
+        assert_eq!(classify("if ($a) { echo 1;"), None);
+        assert_eq!(classify("switch ($a) {"), None);
+        assert_eq!(classify("} while ($a) {"), None);
+    }
+
+    #[test]
+    fn nested_blocks_rewritten() {
+        let input = "<?php if ($a) { ?><ul><?php foreach ($items as $i) { ?><li></li><?php } ?></ul>\
+                     <?php } elseif ($b) { ?>b<?php } else { ?>c<?php } ?>";
+        assert_eq!(php_blocks(input), ["if ($a):", "elseif ($b):", "else:", "endif;"]);
+        let Node::Element { children, .. } = &alternative_syntax(&ast::parse(lexer::tokenize(input)))[1] else {
+            panic!("expected <ul>");
+        };
+        assert_eq!(children[0], Node::PhpBlock("foreach ($items as $i):".into()));
+    }
+
+    #[test]
+    fn ambiguous_blocks_kept() {
+        let crossing = "<div><?php if ($a) { ?></div><?php } ?>";
+        assert_eq!(php_blocks(crossing), ["}"]);
+        let mixed = "<?php if ($a) { $b = 1; ?>x<?php } ?><?php while ($c) { ?>y<?php } ?>";
+        assert_eq!(
+            php_blocks(mixed),
+            ["if ($a) { $b = 1;", "}", "while ($c):", "endwhile;"]
+        );
+        let else_twice = "<?php if ($a) { ?>a<?php } else { ?>b<?php } else { ?>c<?php } ?>";
+        assert_eq!(php_blocks(else_twice), ["if ($a) {", "} else {", "} else {", "}"]);
+    }
+
+    #[test]
+    fn balanced_sibling_blocks_ignored() {
+        let branches = "<?php if ($a) { ?>a<?php } else { ?>b<?php } ?>";
+        let closure = ["<?php $f = function () { return 1; }; ?>", branches].concat();
+        assert_eq!(
+            php_blocks(&closure),
+            ["$f = function () { return 1; };", "if ($a):", "else:", "endif;"]
+        );
+        let js = [
+            branches,
+            "<?php $this->registerJs(<<<JS\n$(function () { init(); });\nJS\n); ?>",
+        ]
+        .concat();
+        assert_eq!(php_blocks(&js)[..3], ["if ($a):", "else:", "endif;"]);
+        let unmatched_in_js = ["<?php $this->registerJs(<<<JS\nif (a) {\nJS\n); ?>", branches].concat();
+        assert_eq!(php_blocks(&unmatched_in_js)[1..], ["if ($a):", "else:", "endif;"]);
+        let commented = ["<?php /* { */ ?>", branches, "<?php // } ?>"].concat();
+        assert_eq!(php_blocks(&commented)[1..4], ["if ($a):", "else:", "endif;"]);
+    }
+
+    #[test]
+    fn trailing_comments_kept_on_rewritten_blocks() {
+        let input = "<?php if ($a) { // check ?>a<?php } else { # other ?>b<?php } // done ?>";
+        assert_eq!(
+            php_blocks(input),
+            ["if ($a): // check", "else: # other", "endif; // done"]
+        );
+    }
+}
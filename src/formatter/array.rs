@@ -0,0 +1,147 @@
+use super::indent::{detect_heredoc, is_heredoc_closing, skip_string_literal};
+use super::php::comment_end;
+use super::split::find_matching_close;
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Index just past `array` plus optional spaces when `array(` starts a long array literal at `i`.
+fn long_array_open(chars: &[char], i: usize) -> Option<usize> {
+    let word: String = chars.get(i..i + 5)?.iter().collect();
+    if !word.eq_ignore_ascii_case("array") || chars.get(i + 5).is_some_and(|&c| is_word_char(c)) {
+        return None;
+    }
+    let before: String = chars[..i].iter().collect();
+    let before = before.trim_end();
+    let member = before.ends_with("->") || before.ends_with("::") || before.ends_with("function");
+    if member
+        || chars[..i]
+            .last()
+            .is_some_and(|&c| is_word_char(c) || matches!(c, '$' | '\\'))
+    {
+        return None;
+    }
+    let mut j = i + 5;
+    while j < chars.len() && chars[j].is_whitespace() {
+        j += 1;
+    }
+    (chars.get(j) == Some(&'(')).then_some(j)
+}
+
+/// Index just past the closing marker of the heredoc or nowdoc opened at `i`, if any.
+pub fn heredoc_end(chars: &[char], i: usize) -> Option<usize> {
+    if !chars[i..].starts_with(&['<', '<', '<']) {
+        return None;
+    }
+    let rest: String = chars[i..].iter().collect();
+    let (opener, body) = rest.split_once('\n')?;
+    let marker = detect_heredoc(opener)?;
+    let mut offset = i + opener.chars().count() + 1;
+    for line in body.split('\n') {
+        if is_heredoc_closing(line, &marker) {
+            let indent = line.len() - line.trim_start().len();
+            return Some(offset + indent + marker.chars().count());
+        }
+        offset += line.chars().count() + 1;
+    }
+    Some(chars.len())
+}
+
+/// Rewrites every `array(...)` literal as `[...]`, nested ones included; strings, comments and
+/// heredocs are copied as written, and an `array(` without its closing `)` is left alone.
+pub fn short_array_syntax(code: &str) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut result = String::with_capacity(code.len());
+    let mut parens: Vec<Option<std::ops::Range<usize>>> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '\'' || ch == '"' {
+            i = skip_string_literal(&chars, i, &mut result);
+            continue;
+        }
+        if let Some(end) = comment_end(&chars, i).or_else(|| heredoc_end(&chars, i)) {
+            result.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if let Some(open) = long_array_open(&chars, i) {
+            let start = result.len();
+            result.extend(&chars[i..=open]);
+            parens.push(Some(start..result.len()));
+            i = open + 1;
+            continue;
+        }
+        match ch {
+            '(' => parens.push(None),
+            ')' => {
+                if let Some(Some(opener)) = parens.pop() {
+                    result.replace_range(opener, "[");
+                    result.push(']');
+                    i += 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        result.push(ch);
+        i += 1;
+    }
+    result
+}
+
+/// Splits an array literal written as `[...]` or `array(...)` into its opener, items and closer.
+pub fn array_literal(code: &str) -> Option<(&str, &str, &str)> {
+    let chars: Vec<char> = code.chars().collect();
+    let open = if code.starts_with('[') {
+        0
+    } else {
+        long_array_open(&chars, 0)?
+    };
+    if find_matching_close(&chars, open)? != chars.len() - 1 {
+        return None;
+    }
+    let inner_start = code.char_indices().nth(open + 1)?.0;
+    let inner_end = code.len() - 1;
+    Some((&code[..inner_start], &code[inner_start..inner_end], &code[inner_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn nested_arrays_converted() {
+        assert_eq!(
+            short_array_syntax("array('class' => 'btn', 'data' => ARRAY ('id' => count($items)))"),
+            "['class' => 'btn', 'data' => ['id' => count($items)]]"
+        );
+        assert_eq!(
+            short_array_syntax("$x = array(\n    'a' => array(1, 2),\n);"),
+            "$x = [\n    'a' => [1, 2],\n];"
+        );
+    }
+
+    #[test]
+    fn strings_comments_and_names_untouched() {
+        let code = "f('array(1)', $array(2), $o->array(3), A::array(4)) // array(5)\n/* array(6) */ (array) $v";
+        assert_eq!(short_array_syntax(code), code);
+        assert_eq!(short_array_syntax("fn(array $a) => array()"), "fn(array $a) => []");
+        assert_eq!(short_array_syntax("$a = array(1,"), "$a = array(1,");
+        let heredoc = "$js = <<<JS\nvar a = array(1);\nJS;\n$b = array();";
+        assert_eq!(
+            short_array_syntax(heredoc),
+            "$js = <<<JS\nvar a = array(1);\nJS;\n$b = [];"
+        );
+    }
+
+    #[test]
+    fn array_literal_parts() {
+        assert_eq!(array_literal("['a', 'b']"), Some(("[", "'a', 'b'", "]")));
+        assert_eq!(array_literal("array ('a')"), Some(("array (", "'a'", ")")));
+        assert_eq!(array_literal("['a'] + ['b']"), None);
+        assert_eq!(array_literal("array_merge($a)"), None);
+    }
+}
@@ -0,0 +1,123 @@
+use super::indent::INDENT;
+use crate::config::{CommentStyle, Config};
+
+/// Reports whether a comment is an IE conditional comment, including the
+/// downlevel-revealed `<!--[if !IE]><!-->` and `<!--<![endif]-->` halves.
+fn is_conditional(raw: &str) -> bool {
+    let trimmed = raw.trim();
+    trimmed.starts_with("[if") || trimmed.starts_with("<![endif]") || trimmed.ends_with("<!")
+}
+
+fn leading_spaces(s: &str) -> &str {
+    &s[..s.len() - s.trim_start().len()]
+}
+
+fn trailing_spaces(s: &str) -> &str {
+    &s[s.trim_end().len()..]
+}
+
+/// Removes the indentation shared by all non-blank lines.
+fn dedent<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| leading_spaces(line).len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(common..).unwrap_or("").trim_end())
+        .collect()
+}
+
+/// Prints `<!--raw-->` at `pad`, where `raw` is the comment text between the delimiters.
+///
+/// Multi-line bodies keep their relative indentation one level deeper than the opener, with a
+/// closing `-->` of its own line back at `pad`. Conditional comments are copied verbatim.
+pub fn format_comment(raw: &str, pad: &str, config: &Config) -> String {
+    if is_conditional(raw) {
+        return format!("{pad}<!--{raw}-->\n");
+    }
+    let (lead, trail) = match config.comment_style {
+        CommentStyle::Normalize => (" ", " "),
+        CommentStyle::Preserve => (leading_spaces(raw), trailing_spaces(raw)),
+    };
+    let lines: Vec<&str> = raw.split('\n').collect();
+    if lines.len() == 1 {
+        let text = raw.trim();
+        if text.is_empty() {
+            return format!("{pad}<!--{lead}-->\n");
+        }
+        return format!("{pad}<!--{lead}{text}{trail}-->\n");
+    }
+
+    let first = lines[0];
+    let rest = &lines[1..];
+    let mut out = String::from(pad);
+    out.push_str("<!--");
+    if !first.trim().is_empty() {
+        out.push_str(lead);
+        out.push_str(first.trim());
+    }
+    let closing_alone = rest.last().is_some_and(|line| line.trim().is_empty());
+    let body = if closing_alone { &rest[..rest.len() - 1] } else { rest };
+    let start = body
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .unwrap_or(body.len());
+    let end = body
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(start, |pos| pos + 1);
+    let body = &body[start..end];
+    for line in dedent(body) {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(&format!("{pad}{INDENT}{line}"));
+        }
+    }
+    if closing_alone {
+        out.push_str(&format!("\n{pad}-->\n"));
+    } else {
+        out.push_str(&format!("{trail}-->\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn spacing_normalized_or_preserved() {
+        let preserve = Config {
+            comment_style: CommentStyle::Preserve,
+            ..Config::default()
+        };
+        assert_eq!(format_comment("note", "  ", &Config::default()), "  <!-- note -->\n");
+        assert_eq!(format_comment("note", "", &preserve), "<!--note-->\n");
+        assert_eq!(format_comment("  note ", "", &preserve), "<!--  note -->\n");
+    }
+
+    #[test]
+    fn multiline_body_reindented() {
+        let raw = "\n            Sidebar\n              - menu\n\n            ";
+        let expected = "    <!--\n        Sidebar\n          - menu\n    -->\n";
+        assert_eq!(format_comment(raw, "    ", &Config::default()), expected);
+        let inline = " first\n        second ";
+        assert_eq!(
+            format_comment(inline, "", &Config::default()),
+            "<!-- first\n    second -->\n"
+        );
+    }
+
+    #[test]
+    fn conditional_comments_verbatim() {
+        let config = Config::default();
+        let raw = "[if lt IE 9]>\n<script src=\"html5shiv.js\"></script>\n<![endif]";
+        assert_eq!(format_comment(raw, "  ", &config), format!("  <!--{raw}-->\n"));
+        assert_eq!(format_comment("[if !IE]><!", "", &config), "<!--[if !IE]><!-->\n");
+        assert_eq!(format_comment("<![endif]", "", &config), "<!--<![endif]-->\n");
+    }
+}
@@ -0,0 +1,454 @@
+use super::echo::echo_statement;
+use super::indent::INDENT;
+
+#[derive(Debug, PartialEq)]
+enum CssItem {
+    Open(String),
+    Close,
+    Declaration(String),
+    Comment(String),
+    /// A PHP block between rules or declarations, such as `<?php if ($dark): ?>`.
+    Php(String),
+    Blank,
+}
+
+struct CssScanner {
+    items: Vec<CssItem>,
+    current: String,
+    newlines: usize,
+    depth: usize,
+    paren_depth: i32,
+}
+
+impl CssScanner {
+    fn push_item(&mut self, item: CssItem) {
+        let after_open = matches!(self.items.last(), None | Some(CssItem::Open(_)));
+        if self.newlines > 1 && !after_open && item != CssItem::Close {
+            self.items.push(CssItem::Blank);
+        }
+        self.items.push(item);
+        self.newlines = 0;
+    }
+
+    fn flush_declaration(&mut self) {
+        let declaration = std::mem::take(&mut self.current);
+        if !declaration.trim().is_empty() {
+            self.push_item(CssItem::Declaration(declaration));
+        }
+    }
+
+    fn open_block(&mut self) {
+        let header = normalize_selector(&std::mem::take(&mut self.current));
+        self.push_item(CssItem::Open(header));
+        self.depth += 1;
+    }
+
+    fn close_block(&mut self) -> Option<()> {
+        self.flush_declaration();
+        self.depth = self.depth.checked_sub(1)?;
+        self.push_item(CssItem::Close);
+        Some(())
+    }
+
+    fn push_char(&mut self, ch: char) {
+        if ch == '\n' && self.current.trim().is_empty() {
+            self.newlines += 1;
+        }
+        match ch {
+            '(' => self.paren_depth += 1,
+            ')' => self.paren_depth -= 1,
+            _ => {}
+        }
+        self.current.push(ch);
+    }
+}
+
+fn copy_php_segment(chars: &[char], start: usize, out: &mut String) -> usize {
+    let len = chars.len();
+    let mut i = start;
+    while i < len {
+        out.push(chars[i]);
+        if chars[i] == '?' && i + 1 < len && chars[i + 1] == '>' {
+            out.push('>');
+            return i + 2;
+        }
+        i += 1;
+    }
+    i
+}
+
+fn copy_quoted(chars: &[char], start: usize, out: &mut String) -> usize {
+    let quote = chars[start];
+    let len = chars.len();
+    out.push(quote);
+    let mut i = start + 1;
+    while i < len && chars[i] != quote {
+        if chars[i] == '\\' && i + 1 < len {
+            out.push(chars[i]);
+            i += 1;
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    if i < len {
+        out.push(chars[i]);
+        i += 1;
+    }
+    i
+}
+
+fn collect_comment(chars: &[char], start: usize) -> (String, usize) {
+    let len = chars.len();
+    let mut comment = String::from("/*");
+    let mut i = start + 2;
+    while i < len {
+        if chars[i] == '*' && i + 1 < len && chars[i + 1] == '/' {
+            comment.push_str("*/");
+            return (comment, i + 2);
+        }
+        comment.push(chars[i]);
+        i += 1;
+    }
+    (comment, i)
+}
+
+fn is_php_open(chars: &[char], i: usize) -> bool {
+    chars[i] == '<' && chars.get(i + 1) == Some(&'?')
+}
+
+/// Reports whether a `<?...?>` segment prints a value, as in `<?= $color ?>`.
+fn is_php_echo(segment: &str) -> bool {
+    let code = segment.trim_start_matches("<?").trim_end_matches("?>");
+    code.starts_with('=') || echo_statement(code.strip_prefix("php").unwrap_or(code)).is_some()
+}
+
+fn is_comment_open(chars: &[char], i: usize) -> bool {
+    chars[i] == '/' && chars.get(i + 1) == Some(&'*')
+}
+
+fn scan(source: &str) -> Option<Vec<CssItem>> {
+    let chars: Vec<char> = source.chars().collect();
+    let len = chars.len();
+    let mut scanner = CssScanner {
+        items: Vec::new(),
+        current: String::new(),
+        newlines: 0,
+        depth: 0,
+        paren_depth: 0,
+    };
+    let mut i = 0;
+    while i < len {
+        let ch = chars[i];
+        if is_php_open(&chars, i) {
+            let mut segment = String::new();
+            i = copy_php_segment(&chars, i, &mut segment);
+            if scanner.current.trim().is_empty() && !is_php_echo(&segment) {
+                scanner.current.clear();
+                scanner.push_item(CssItem::Php(segment));
+            } else {
+                scanner.current.push_str(&segment);
+            }
+        } else if ch == '"' || ch == '\'' {
+            i = copy_quoted(&chars, i, &mut scanner.current);
+        } else if is_comment_open(&chars, i) {
+            let (comment, next) = collect_comment(&chars, i);
+            if scanner.current.trim().is_empty() {
+                scanner.current.clear();
+                scanner.push_item(CssItem::Comment(comment));
+            } else {
+                scanner.current.push_str(&comment);
+            }
+            i = next;
+        } else {
+            match ch {
+                '{' if scanner.paren_depth <= 0 => scanner.open_block(),
+                '}' if scanner.paren_depth <= 0 => scanner.close_block()?,
+                ';' if scanner.paren_depth <= 0 => scanner.flush_declaration(),
+                _ => scanner.push_char(ch),
+            }
+            i += 1;
+        }
+    }
+    if scanner.depth != 0 {
+        return None;
+    }
+    scanner.flush_declaration();
+    Some(scanner.items)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let chars: Vec<char> = text.trim().chars().collect();
+    let len = chars.len();
+    let mut out = String::with_capacity(len);
+    let mut i = 0;
+    while i < len {
+        let ch = chars[i];
+        if is_php_open(&chars, i) {
+            i = copy_php_segment(&chars, i, &mut out);
+        } else if ch == '"' || ch == '\'' {
+            i = copy_quoted(&chars, i, &mut out);
+        } else if is_comment_open(&chars, i) {
+            let (comment, next) = collect_comment(&chars, i);
+            out.push_str(&comment);
+            i = next;
+        } else if ch.is_whitespace() {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+            i += 1;
+        } else {
+            out.push(ch);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn find_top_level(text: &str, target: char) -> Vec<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut positions = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut in_php = false;
+    let mut in_comment = false;
+    for (idx, &(pos, ch)) in chars.iter().enumerate() {
+        let next = chars.get(idx + 1).map(|&(_, c)| c);
+        let prev = idx.checked_sub(1).map(|p| chars[p].1);
+        if in_php {
+            in_php = !(ch == '?' && next == Some('>'));
+        } else if in_comment {
+            in_comment = !(ch == '/' && prev == Some('*'));
+        } else if let Some(q) = quote {
+            if ch == q {
+                quote = None;
+            }
+        } else if ch == '/' && next == Some('*') {
+            in_comment = true;
+        } else if ch == '<' && next == Some('?') {
+            in_php = true;
+        } else if ch == '"' || ch == '\'' {
+            quote = Some(ch);
+        } else if matches!(ch, '(' | '[') {
+            depth += 1;
+        } else if matches!(ch, ')' | ']') {
+            depth -= 1;
+        } else if ch == target && depth == 0 {
+            positions.push(pos);
+        }
+    }
+    positions
+}
+
+fn normalize_selector(header: &str) -> String {
+    let collapsed = collapse_whitespace(header);
+    if collapsed.starts_with('@') {
+        return collapsed;
+    }
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for pos in find_top_level(&collapsed, ',') {
+        parts.push(collapsed[start..pos].trim());
+        start = pos + 1;
+    }
+    parts.push(collapsed[start..].trim());
+    parts.join(", ")
+}
+
+fn normalize_declaration(declaration: &str) -> String {
+    let collapsed = collapse_whitespace(declaration);
+    let Some(&colon) = find_top_level(&collapsed, ':').first() else {
+        return collapsed;
+    };
+    let property = collapsed[..colon].trim();
+    let value = collapsed[colon + 1..].trim();
+    if property.is_empty() || property.contains(' ') {
+        return collapsed;
+    }
+    format!("{property}: {value}")
+}
+
+fn push_line(out: &mut String, pad: &str, level: usize, line: &str) {
+    out.push_str(pad);
+    out.push_str(&INDENT.repeat(level));
+    out.push_str(line);
+    out.push('\n');
+}
+
+fn push_comment(out: &mut String, pad: &str, level: usize, comment: &str) {
+    for (idx, line) in comment.lines().enumerate() {
+        let trimmed = line.trim();
+        if idx > 0 && trimmed.starts_with('*') {
+            push_line(out, pad, level, &format!(" {trimmed}"));
+        } else if idx == 0 || !trimmed.is_empty() {
+            push_line(out, pad, level, trimmed);
+        } else {
+            out.push('\n');
+        }
+    }
+}
+
+/// Formats a stylesheet with one declaration per line, indenting every line with `pad`.
+///
+/// Returns `None` when the braces are unbalanced so the caller can keep the source as is.
+pub fn format_css(source: &str, pad: &str) -> Option<String> {
+    let items = scan(source)?;
+    let mut out = String::new();
+    let mut level = 0usize;
+    for item in &items {
+        match item {
+            CssItem::Open(header) if header.is_empty() => {
+                push_line(&mut out, pad, level, "{");
+                level += 1;
+            }
+            CssItem::Open(header) => {
+                push_line(&mut out, pad, level, &format!("{header} {{"));
+                level += 1;
+            }
+            CssItem::Close => {
+                level = level.saturating_sub(1);
+                push_line(&mut out, pad, level, "}");
+            }
+            CssItem::Declaration(declaration) => {
+                push_line(
+                    &mut out,
+                    pad,
+                    level,
+                    &format!("{};", normalize_declaration(declaration)),
+                );
+            }
+            CssItem::Comment(comment) => push_comment(&mut out, pad, level, comment),
+            CssItem::Php(code) => push_line(&mut out, pad, level, code),
+            CssItem::Blank => out.push('\n'),
+        }
+    }
+    Some(out)
+}
+
+/// Normalizes the declarations of an inline `style` attribute to `prop: value; prop: value`.
+pub fn format_style_attribute(value: &str) -> Option<String> {
+    let items = scan(value)?;
+    let mut declarations = Vec::new();
+    for item in &items {
+        match item {
+            CssItem::Declaration(declaration) => declarations.push(normalize_declaration(declaration)),
+            CssItem::Blank => {}
+            _ => return None,
+        }
+    }
+    if declarations.is_empty() {
+        return None;
+    }
+    Some(declarations.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn one_declaration_per_line() {
+        let expected = "\
+.a > .b {
+    color: red;
+    font-size: 14px;
+}
+";
+        assert_eq!(
+            format_css(".a > .b { color:red;font-size :  14px }", "").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn nested_media_rule() {
+        let input = "@media (max-width: 600px) { .a { display:none; } }";
+        let expected = concat!(
+            "    @media (max-width: 600px) {\n",
+            "        .a {\n",
+            "            display: none;\n",
+            "        }\n",
+            "    }\n",
+        );
+        assert_eq!(format_css(input, "    ").unwrap(), expected);
+    }
+
+    #[test]
+    fn selector_list_spacing() {
+        assert_eq!(
+            format_css("h1,h2 ,h3{margin:0}", "").unwrap(),
+            "h1, h2, h3 {\n    margin: 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn comments_preserved() {
+        let input = "/* header */\n.a { /* inner */ color: red; }";
+        let expected = "\
+/* header */
+.a {
+    /* inner */
+    color: red;
+}
+";
+        assert_eq!(format_css(input, "").unwrap(), expected);
+    }
+
+    #[test]
+    fn comment_inside_declaration_kept_whole() {
+        assert_eq!(
+            format_css(".a { color:red /* a; b {c} */; margin :0 }", "").unwrap(),
+            ".a {\n    color: red /* a; b {c} */;\n    margin: 0;\n}\n"
+        );
+        assert_eq!(
+            format_style_attribute("color:red /* a;  b */;top:0").unwrap(),
+            "color: red /* a;  b */; top: 0"
+        );
+    }
+
+    #[test]
+    fn blank_line_between_rules_preserved() {
+        let input = ".a { color: red; }\n\n\n.b { color: blue; }";
+        assert_eq!(
+            format_css(input, "").unwrap(),
+            ".a {\n    color: red;\n}\n\n.b {\n    color: blue;\n}\n"
+        );
+    }
+
+    #[test]
+    fn php_echo_in_value_untouched() {
+        let input = ".a { color: <?= $color ?>; width:<?= $w ?>px }";
+        assert_eq!(
+            format_css(input, "").unwrap(),
+            ".a {\n    color: <?= $color ?>;\n    width: <?= $w ?>px;\n}\n"
+        );
+    }
+
+    #[test]
+    fn semicolon_inside_url_kept() {
+        let input = ".a{background:url(data:image/png;base64,AAA)}";
+        assert_eq!(
+            format_css(input, "").unwrap(),
+            ".a {\n    background: url(data:image/png;base64,AAA);\n}\n"
+        );
+    }
+
+    #[test]
+    fn unbalanced_braces_rejected() {
+        assert_eq!(format_css(".a { color: red;", ""), None);
+        assert_eq!(format_css(".a { color: red; }}", ""), None);
+    }
+
+    #[test]
+    fn style_attribute_normalized() {
+        assert_eq!(
+            format_style_attribute("color:red;font-size:12px;").unwrap(),
+            "color: red; font-size: 12px"
+        );
+    }
+
+    #[test]
+    fn style_attribute_with_block_rejected() {
+        assert_eq!(format_style_attribute(".a { color: red }"), None);
+    }
+}
@@ -1,10 +1,61 @@
-use super::indent::{INDENT, MAX_LINE_LENGTH, contains_outside_strings};
-use super::php::{format_php_code, join_php_lines, split_by_args, split_by_chain, split_by_concat};
+use super::indent::{
+    INDENT, MAX_LINE_LENGTH, contains_outside_strings, count_leading_closers, count_semicolons_outside_parens,
+    skip_string_literal,
+};
+use super::php::{
+    comment_end, format_php_code, join_php_lines, split_by_args, split_by_chain, split_by_concat,
+    split_trailing_comment,
+};
 use super::split::{expand_bare_array, expand_nested_array, find_ternary_positions, try_split_long_line};
+use crate::config::{ChainWrap, ConcatSpacing, Config, EchoStyle};
 
-pub fn is_single_echo_block(code: &str) -> bool {
+/// Delimiters around a printed echo: `<?= ` and ` ?>`, or `<?php echo ` and `; ?>`, with any
+/// trailing comment kept just before `?>`.
+struct Delimiters {
+    open: &'static str,
+    close: String,
+}
+
+impl Delimiters {
+    fn new(comment: &str, config: &Config) -> Self {
+        let (open, semicolon) = match config.echo_style {
+            EchoStyle::Short => ("<?= ", ""),
+            EchoStyle::Long => ("<?php echo ", ";"),
+        };
+        let comment = if comment.is_empty() {
+            String::new()
+        } else {
+            format!(" {comment}")
+        };
+        Self {
+            open,
+            close: format!("{semicolon}{comment} ?>"),
+        }
+    }
+}
+
+/// Splits echoed code into its expression, without the statement's `;`, and a trailing comment.
+fn split_echo_tail(code: &str) -> (&str, &str) {
+    let (expr, comment) = split_trailing_comment(code);
+    (expr.strip_suffix(';').unwrap_or(expr).trim(), comment)
+}
+
+/// Returns the echoed part of a PHP block holding a single `echo` or `print` statement,
+/// possibly spread over several lines and followed by a comment.
+pub fn echo_statement(code: &str) -> Option<&str> {
     let trimmed = code.trim();
-    trimmed.starts_with("echo ") && !trimmed.contains('\n') && trimmed.matches(';').count() <= 1
+    let keyword = trimmed.split(|c: char| c.is_whitespace() || c == '(').next()?;
+    if !keyword.eq_ignore_ascii_case("echo") && !keyword.eq_ignore_ascii_case("print") {
+        return None;
+    }
+    let rest = trimmed[keyword.len()..].trim_start();
+    let (expr, comment) = split_echo_tail(rest);
+    let single = count_semicolons_outside_parens(expr) == 0 && !comment.contains('\n');
+    (single && !expr.is_empty()).then_some(rest)
+}
+
+pub fn is_single_echo_block(code: &str) -> bool {
+    echo_statement(code).is_some_and(|rest| !rest.contains('\n'))
 }
 
 pub fn is_echo_block_opener(code: &str) -> bool {
@@ -17,10 +68,31 @@ pub fn is_echo_block_closer(code: &str) -> bool {
     trimmed.contains("endtag(") || trimmed.contains("::end(")
 }
 
-fn format_echo_chain(parts: &[String], pad: &str) -> String {
+/// Prints echoed code on one line in the configured echo style, for inline content.
+pub fn format_inline_echo(code: &str, config: &Config) -> String {
+    let (expr, comment) = split_echo_tail(code);
+    let tags = Delimiters::new(comment, config);
+    format!("{}{}{}", tags.open, format_php_code(expr, config), tags.close)
+}
+
+/// Number of chain parts printed on the receiver line: the receiver, plus the first call when
+/// `chain_first_call_inline` is set.
+fn chain_head(config: &Config) -> usize {
+    if config.chain_first_call_inline { 2 } else { 1 }
+}
+
+fn format_echo_chain(parts: &[String], pad: &str, config: &Config, tags: &Delimiters) -> String {
     let chain_pad = format!("{pad}{INDENT}");
-    let mut result = format!("{pad}<?= {}{}", parts[0], parts[1]);
-    for part in &parts[2..] {
+    let head = chain_head(config);
+    let mut result = format!("{pad}{}{}", tags.open, parts[..head].concat());
+    for (index, part) in parts.iter().enumerate().skip(head) {
+        let line_start = result.rfind('\n').map_or(0, |pos| pos + 1);
+        let tail = if index == parts.len() - 1 { tags.close.len() } else { 0 };
+        let fits = result.len() - line_start + part.len() + tail <= MAX_LINE_LENGTH;
+        if config.chain_wrap == ChainWrap::Fill && fits {
+            result.push_str(part);
+            continue;
+        }
         let part_line_len = chain_pad.len() + part.len();
         if part_line_len > MAX_LINE_LENGTH {
             if let Some(split) = try_split_long_line(part, &chain_pad) {
@@ -31,21 +103,27 @@ fn format_echo_chain(parts: &[String], pad: &str) -> String {
         }
         result.push_str(&format!("\n{chain_pad}{part}"));
     }
-    result.push_str(" ?>\n");
+    result.push_str(&tags.close);
+    result.push('\n');
     result
 }
 
-fn format_echo_concat(parts: &[String], pad: &str) -> String {
+fn format_echo_concat(parts: &[String], pad: &str, config: &Config, tags: &Delimiters) -> String {
     let concat_pad = format!("{pad}{INDENT}");
-    let mut result = format!("{pad}<?= {}", parts[0]);
+    let dot = match config.concat_spacing {
+        ConcatSpacing::Spaced => ". ",
+        ConcatSpacing::Tight => ".",
+    };
+    let mut result = format!("{pad}{}{}", tags.open, parts[0]);
     for part in &parts[1..] {
-        result.push_str(&format!("\n{concat_pad}. {part}"));
+        result.push_str(&format!("\n{concat_pad}{dot}{part}"));
     }
-    result.push_str(" ?>\n");
+    result.push_str(&tags.close);
+    result.push('\n');
     result
 }
 
-fn split_ternary(code: &str, pad: &str) -> Option<String> {
+fn split_ternary(code: &str, pad: &str, tags: &Delimiters) -> Option<String> {
     let (q_pos, c_pos) = find_ternary_positions(code)?;
 
     let condition = code[..q_pos].trim_end();
@@ -54,36 +132,89 @@ fn split_ternary(code: &str, pad: &str) -> Option<String> {
 
     let inner_pad = format!("{pad}{INDENT}");
     Some(format!(
-        "{pad}<?= {condition}\n{inner_pad}? {true_val}\n{inner_pad}: {false_val} ?>\n"
+        "{pad}{}{condition}\n{inner_pad}? {true_val}\n{inner_pad}: {false_val}{}\n",
+        tags.open, tags.close
     ))
 }
 
-pub fn format_echo(code: &str, pad: &str) -> String {
-    let joined = join_php_lines(code);
-    let formatted = format_php_code(&joined);
-    let combined = format!("{formatted} ?>");
-    let single = format!("{pad}<?= {combined}");
+/// Updates `open` with the brackets of `line`, recording the line index that opened each one.
+fn track_brackets(line: &str, index: usize, open: &mut Vec<usize>) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut scratch = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if matches!(chars[i], '\'' | '"') {
+            i = skip_string_literal(&chars, i, &mut scratch);
+            continue;
+        }
+        if let Some(end) = comment_end(&chars, i) {
+            i = end;
+            continue;
+        }
+        match chars[i] {
+            '(' | '[' | '{' => open.push(index),
+            ')' | ']' | '}' => {
+                open.pop();
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Prints a multi-line echo line by line because a `//` or `#` comment sits between its lines.
+///
+/// A line leaving brackets open indents the following lines by one level however many it opens,
+/// and the line closing them goes back to its level.
+fn format_echo_lines(expr: &str, pad: &str, config: &Config, tags: &Delimiters) -> String {
+    let lines: Vec<&str> = expr.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let first = lines.first().copied().unwrap_or_default();
+    let mut result = format!("{pad}{}{}", tags.open, format_php_code(first, config));
+    let mut open = Vec::new();
+    track_brackets(first, 0, &mut open);
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        let closers = count_leading_closers(line).min(open.len());
+        let mut levels = open[..open.len() - closers].to_vec();
+        levels.dedup();
+        let level = levels.len().max(usize::from(closers == 0));
+        let line_pad = INDENT.repeat(level);
+        result.push_str(&format!("\n{pad}{line_pad}{}", format_php_code(line, config)));
+        track_brackets(line, index, &mut open);
+    }
+    result.push_str(&tags.close);
+    result.push('\n');
+    result
+}
+
+pub fn format_echo(code: &str, pad: &str, config: &Config) -> String {
+    let (expr, comment) = split_echo_tail(code);
+    let tags = Delimiters::new(comment, config);
+    let Some(joined) = join_php_lines(expr) else {
+        return format_echo_lines(expr, pad, config, &tags);
+    };
+    let formatted = format_php_code(&joined, config);
+    let single = format!("{pad}{}{formatted}{}", tags.open, tags.close);
 
     if single.len() <= MAX_LINE_LENGTH {
         return format!("{single}\n");
     }
 
     let parts = split_by_chain(&formatted);
-    if parts.len() > 2 {
-        return format_echo_chain(&parts, pad);
+    if parts.len() > chain_head(config) && parts.len() > config.chain_min_calls {
+        return format_echo_chain(&parts, pad, config, &tags);
     }
 
-    if let Some(result) = split_ternary(&formatted, pad) {
+    if let Some(result) = split_ternary(&formatted, pad, &tags) {
         return result;
     }
 
     let concat_parts = split_by_concat(&formatted);
     if concat_parts.len() > 1 {
-        return format_echo_concat(&concat_parts, pad);
+        return format_echo_concat(&concat_parts, pad, config, &tags);
     }
 
     if let Some((prefix, args, suffix)) = split_by_args(&formatted) {
-        let mut result = format!("{pad}<?= {prefix}\n");
+        let mut result = format!("{pad}{}{prefix}\n", tags.open);
         let inner_pad = format!("{pad}{INDENT}");
         for arg in &args {
             let line_len = inner_pad.len() + arg.len() + 1;
@@ -109,13 +240,13 @@ pub fn format_echo(code: &str, pad: &str) -> String {
             }
             result.push_str(&format!("{inner_pad}{arg},\n"));
         }
-        result.push_str(&format!("{pad}{suffix} ?>\n"));
+        result.push_str(&format!("{pad}{suffix}{}\n", tags.close));
         return result;
     }
 
     if let Some(split) = try_split_long_line(&formatted, pad) {
         let trimmed = split.trim_start().trim_end_matches('\n');
-        return format!("{pad}<?= {trimmed} ?>\n");
+        return format!("{pad}{}{trimmed}{}\n", tags.open, tags.close);
     }
 
     format!("{single}\n")
@@ -1,13 +1,28 @@
+use super::alt_syntax::alternative_syntax;
+use super::array::short_array_syntax;
+use super::comment::format_comment;
+use super::css::format_css;
 use super::docblock::{emit_docblock_php, expand_single_line_docblock, is_docblock_only};
-use super::echo::{contains_break, format_echo, is_echo_block_closer, is_echo_block_opener, is_single_echo_block};
+use super::echo::{
+    contains_break, echo_statement, format_echo, format_inline_echo, is_echo_block_closer, is_echo_block_opener,
+    is_single_echo_block,
+};
+use super::heredoc::format_embedded_heredocs;
+use super::html::{
+    attribute_value, normalize_attributes, normalize_foreign_attributes, normalize_name, void_tag_end, wrap_class_list,
+};
 use super::indent::{
     INDENT, MAX_LINE_LENGTH, count_semicolons_outside_parens, has_switch_case, is_header_php_block,
     is_php_block_closer, is_php_block_opener, is_switch_case_peer, reindent_php_block, split_header_and_opener,
 };
+use super::js::reindent_js;
+use super::json::format_json;
 use super::php::format_php_code;
+use super::prose::{Piece, reflow};
 use super::split::find_ternary_positions;
-use crate::parser::ast::Node;
-use crate::parser::lexer::Attribute;
+use crate::config::{Config, ProseWrap, UnclosedTags, WrapAttributes};
+use crate::parser::ast::{self, Node};
+use crate::parser::lexer::{self, Attribute};
 
 const VOID_ELEMENTS: &[&str] = &[
     "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
@@ -15,6 +30,36 @@ const VOID_ELEMENTS: &[&str] = &[
 
 const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea"];
 
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "i", "kbd", "mark", "q", "s", "samp", "small",
+    "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "listing", "xmp", "plaintext"];
+
+const FOREIGN_ROOTS: &[&str] = &["svg", "math"];
+
+const FOREIGN_INTEGRATION_POINTS: &[&str] = &["foreignObject", "annotation-xml"];
+
+const JS_SCRIPT_TYPES: &[&str] = &[
+    "text/javascript",
+    "application/javascript",
+    "application/x-javascript",
+    "text/ecmascript",
+    "application/ecmascript",
+    "module",
+];
+
+const JSON_SCRIPT_TYPES: &[&str] = &["application/json", "application/ld+json", "importmap"];
+
+const TEMPLATE_SCRIPT_TYPES: &[&str] = &[
+    "text/html",
+    "text/template",
+    "text/x-template",
+    "text/x-handlebars-template",
+    "text/ng-template",
+];
+
 fn is_void_element(name: &str) -> bool {
     VOID_ELEMENTS.contains(&name.to_lowercase().as_str())
 }
@@ -24,19 +69,16 @@ fn format_attributes(attrs: &[Attribute]) -> String {
         return String::new();
     }
 
-    let parts: Vec<String> = attrs
-        .iter()
-        .map(|a| match &a.value {
-            Some(v) => format!("{}=\"{}\"", a.name, v),
-            None => a.name.clone(),
-        })
-        .collect();
+    let parts: Vec<String> = attrs.iter().map(format_attribute).collect();
 
     format!(" {}", parts.join(" "))
 }
 
+/// Prints an attribute, single-quoting a value that holds `"` but no `'`, such as the JSON in
+/// `data-options='{"a":1}'`, so it stays a valid attribute.
 fn format_attribute(attr: &Attribute) -> String {
     match &attr.value {
+        Some(v) if v.contains('"') && !v.contains('\'') && !v.contains("<?") => format!("{}='{}'", attr.name, v),
         Some(v) => format!("{}=\"{}\"", attr.name, v),
         None => attr.name.clone(),
     }
@@ -45,29 +87,142 @@ fn format_attribute(attr: &Attribute) -> String {
 struct TagParams<'a> {
     name: &'a str,
     attributes: &'a [Attribute],
-    self_closing: bool,
+    tail: &'a str,
+    /// The source has an end tag for the element.
+    closed: bool,
+}
+
+impl TagParams<'_> {
+    /// The end tag to print, or nothing for an unclosed element under `unclosed_tags = "preserve"`.
+    fn close(&self, config: &Config) -> String {
+        if self.closed || config.unclosed_tags != UnclosedTags::Preserve {
+            format!("</{}>", self.name)
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Context shared by sibling nodes.
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    config: &'a Config,
+    /// Inside `<svg>` or `<math>`: names keep their case and any empty element self-closes.
+    foreign: bool,
+    /// Inside `<pre>`, where text is never reflowed.
+    preformatted: bool,
+}
+
+impl<'a> Scope<'a> {
+    fn html(config: &'a Config) -> Self {
+        Self {
+            config,
+            foreign: false,
+            preformatted: false,
+        }
+    }
+
+    fn enter(self, name: &str) -> (bool, Self) {
+        let foreign = self.foreign || FOREIGN_ROOTS.iter().any(|root| root.eq_ignore_ascii_case(name));
+        let children = Self {
+            foreign: foreign && !FOREIGN_INTEGRATION_POINTS.iter().any(|p| p.eq_ignore_ascii_case(name)),
+            preformatted: self.preformatted || PREFORMATTED_ELEMENTS.contains(&name.to_lowercase().as_str()),
+            ..self
+        };
+        (foreign, children)
+    }
+}
+
+fn wraps_regardless_of_length(attributes: &[Attribute], config: &Config) -> bool {
+    match config.wrap_attributes {
+        WrapAttributes::ForceExpandMultiline => attributes.len() > config.wrap_attributes_threshold,
+        WrapAttributes::Preserve => attributes.iter().any(|attr| attr.line_break),
+        WrapAttributes::Auto | WrapAttributes::Aligned => false,
+    }
 }
 
-fn emit_open_tag(tag: &TagParams, pad: &str, output: &mut String) {
+fn attribute_line(lead: &str, attr: &Attribute, config: &Config) -> String {
+    let formatted = format_attribute(attr);
+    let name = &attr.name;
+    let wrapped = attr
+        .value
+        .as_deref()
+        .filter(|_| config.normalize_class && name.eq_ignore_ascii_case("class"))
+        .filter(|_| lead.len() + formatted.len() > MAX_LINE_LENGTH)
+        .and_then(|value| {
+            let continuation = format!("{}{INDENT}", " ".repeat(lead.len()));
+            wrap_class_list(value, lead.len() + name.len() + 2, &continuation)
+        });
+    match wrapped {
+        Some(value) => format!("{lead}{name}=\"{value}\""),
+        None => format!("{lead}{formatted}"),
+    }
+}
+
+fn expanded_attribute_lines(tag: &TagParams, pad: &str, config: &Config) -> Vec<String> {
+    let attr_pad = format!("{pad}{INDENT}");
+    let mut lines = vec![format!("{pad}<{}", tag.name)];
+    lines.extend(
+        tag.attributes
+            .iter()
+            .map(|attr| attribute_line(&attr_pad, attr, config)),
+    );
+    lines
+}
+
+fn aligned_attribute_lines(tag: &TagParams, pad: &str, config: &Config) -> Vec<String> {
+    let align = format!("{pad}{}", " ".repeat(tag.name.len() + 2));
+    let mut lines = Vec::new();
+    for (idx, attr) in tag.attributes.iter().enumerate() {
+        if idx == 0 {
+            lines.push(attribute_line(&format!("{pad}<{} ", tag.name), attr, config));
+        } else {
+            lines.push(attribute_line(&align, attr, config));
+        }
+    }
+    lines
+}
+
+fn preserved_attribute_lines(tag: &TagParams, pad: &str, config: &Config) -> Vec<String> {
+    let attr_pad = format!("{pad}{INDENT}");
+    let mut lines = vec![format!("{pad}<{}", tag.name)];
+    for attr in tag.attributes {
+        if attr.line_break {
+            lines.push(attribute_line(&attr_pad, attr, config));
+        } else if let Some(last) = lines.last_mut() {
+            last.push(' ');
+            last.push_str(&format_attribute(attr));
+        }
+    }
+    lines
+}
+
+fn emit_open_tag(tag: &TagParams, pad: &str, config: &Config, output: &mut String) {
     let attrs = format_attributes(tag.attributes);
-    let tail = if tag.self_closing { " />" } else { ">" };
+    let tail = tag.tail;
     let name = tag.name;
     let single = format!("{pad}<{name}{attrs}{tail}");
 
-    if tag.attributes.is_empty() || single.len() <= MAX_LINE_LENGTH {
+    let too_long = single.len() > MAX_LINE_LENGTH && config.wrap_attributes != WrapAttributes::Preserve;
+    if tag.attributes.is_empty() || !(too_long || wraps_regardless_of_length(tag.attributes, config)) {
         output.push_str(&single);
         output.push('\n');
         return;
     }
 
-    output.push_str(&format!("{pad}<{name}\n"));
-    let attr_pad = format!("{pad}{INDENT}");
-    for attr in tag.attributes {
-        output.push_str(&attr_pad);
-        output.push_str(&format_attribute(attr));
+    let mut lines = match config.wrap_attributes {
+        WrapAttributes::Aligned => aligned_attribute_lines(tag, pad, config),
+        WrapAttributes::Preserve => preserved_attribute_lines(tag, pad, config),
+        WrapAttributes::Auto | WrapAttributes::ForceExpandMultiline => expanded_attribute_lines(tag, pad, config),
+    };
+    match lines.last_mut() {
+        Some(last) if config.bracket_same_line => last.push_str(tail),
+        _ => lines.push(format!("{pad}{tail}")),
+    }
+    for line in lines {
+        output.push_str(&line);
         output.push('\n');
     }
-    output.push_str(&format!("{pad}{tail}\n"));
 }
 
 fn is_inline_content(children: &[Node]) -> bool {
@@ -78,105 +233,199 @@ fn is_inline_content(children: &[Node]) -> bool {
     })
 }
 
-fn format_inline(name: &str, attributes: &[Attribute], children: &[Node]) -> String {
-    let attrs = format_attributes(attributes);
+fn format_inline(tag: &TagParams, children: &[Node], config: &Config) -> String {
+    let attrs = format_attributes(tag.attributes);
     let content: String = children
         .iter()
         .map(|c| match c {
             Node::Text(s) => s.trim().to_string(),
-            Node::PhpEcho(s) => format!("<?= {} ?>", format_php_code(s)),
-            Node::PhpBlock(s) if is_single_echo_block(s) => {
-                let expr = s.trim().strip_prefix("echo ").unwrap_or(s);
-                let expr = expr.strip_suffix(';').unwrap_or(expr).trim();
-                format!("<?= {} ?>", format_php_code(expr))
-            }
+            Node::PhpEcho(s) => format_inline_echo(s, config),
+            Node::PhpBlock(s) => echo_statement(s).map_or_else(String::new, |expr| format_inline_echo(expr, config)),
             _ => String::new(),
         })
         .collect();
-    format!("<{name}{attrs}>{content}</{name}>")
+    format!("<{}{attrs}>{content}{}", tag.name, tag.close(config))
 }
 
-fn emit_element(name: &str, attributes: &[Attribute], children: &[Node], ctx: (usize, &mut String)) {
-    let (depth, output) = ctx;
+fn emit_raw_text_verbatim(text: &str, pad: &str, output: &mut String) {
+    let trimmed = text.trim_start_matches('\n').trim_end();
+    if trimmed.is_empty() {
+        return;
+    }
+    for line in trimmed.lines() {
+        if line.chars().next().is_some_and(char::is_whitespace) {
+            output.push_str(line);
+        } else {
+            output.push_str(pad);
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+}
+
+fn is_css_style(attributes: &[Attribute]) -> bool {
+    attribute_value(attributes, "type").is_none_or(|t| t.trim().eq_ignore_ascii_case("text/css"))
+}
+
+fn is_js_script(attributes: &[Attribute]) -> bool {
+    attribute_value(attributes, "type").is_none_or(|t| JS_SCRIPT_TYPES.contains(&t.trim().to_lowercase().as_str()))
+}
+
+fn is_json_script(attributes: &[Attribute]) -> bool {
+    attribute_value(attributes, "type").is_some_and(|t| JSON_SCRIPT_TYPES.contains(&t.trim().to_lowercase().as_str()))
+}
+
+fn is_template_script(attributes: &[Attribute]) -> bool {
+    attribute_value(attributes, "type")
+        .is_some_and(|t| TEMPLATE_SCRIPT_TYPES.contains(&t.trim().to_lowercase().as_str()))
+}
+
+fn format_template_script(text: &str, depth: usize, config: &Config) -> Option<String> {
+    let tokens = lexer::tokenize(text);
+    if !ast::is_well_formed(&tokens) {
+        return None;
+    }
+    let mut output = String::new();
+    format_nodes(&ast::parse(tokens), depth + 1, Scope::html(config), &mut output);
+    Some(output)
+}
+
+fn format_raw_text(tag: &TagParams, text: &str, depth: usize, config: &Config) -> Option<String> {
+    let inner_pad = INDENT.repeat(depth + 1);
+    if tag.name.eq_ignore_ascii_case("style") && config.format_css && is_css_style(tag.attributes) {
+        return format_css(text, &inner_pad);
+    }
+    if !tag.name.eq_ignore_ascii_case("script") {
+        return None;
+    }
+    if config.format_json && is_json_script(tag.attributes) {
+        return format_json(text, &inner_pad, config.json_style);
+    }
+    if config.format_templates && is_template_script(tag.attributes) {
+        return format_template_script(text, depth, config);
+    }
+    if config.format_js && is_js_script(tag.attributes) {
+        return reindent_js(text, &inner_pad);
+    }
+    None
+}
+
+fn emit_raw_text_element(tag: &TagParams, children: &[Node], ctx: (usize, &Config, &mut String)) {
+    let (depth, config, output) = ctx;
     let pad = INDENT.repeat(depth);
-    if RAW_TEXT_ELEMENTS.contains(&name.to_lowercase().as_str()) {
-        emit_open_tag(
-            &TagParams {
-                name,
-                attributes,
-                self_closing: false,
-            },
-            &pad,
-            output,
-        );
-        for child in children {
-            if let Node::Text(s) = child {
-                let trimmed = s.trim_start_matches('\n').trim_end();
-                if !trimmed.is_empty() {
-                    for line in trimmed.lines() {
-                        if line.chars().next().is_some_and(char::is_whitespace) {
-                            output.push_str(line);
-                        } else {
-                            output.push_str(&pad);
-                            output.push_str(line);
-                        }
-                        output.push('\n');
-                    }
-                }
+    emit_open_tag(tag, &pad, config, output);
+    for child in children {
+        if let Node::Text(s) = child {
+            match format_raw_text(tag, s, depth, config) {
+                Some(formatted) => output.push_str(&formatted),
+                None => emit_raw_text_verbatim(s, &pad, output),
             }
         }
-        output.push_str(&format!("{pad}</{name}>\n"));
-    } else if children.is_empty() && is_void_element(name) {
-        emit_open_tag(
-            &TagParams {
-                name,
-                attributes,
-                self_closing: true,
-            },
-            &pad,
-            output,
-        );
-    } else if is_inline_content(children) {
-        let inline = format_inline(name, attributes, children);
+    }
+    push_close_tag(tag, &pad, config, output);
+}
+
+fn push_close_tag(tag: &TagParams, pad: &str, config: &Config, output: &mut String) {
+    let close = tag.close(config);
+    if !close.is_empty() {
+        output.push_str(&format!("{pad}{close}\n"));
+    }
+}
+
+fn emit_element(tag: &TagParams, children: &[Node], ctx: (usize, Scope, &mut String)) {
+    let (depth, scope, output) = ctx;
+    let (foreign, children_scope) = scope.enter(tag.name);
+    let config = scope.config;
+    let pad = INDENT.repeat(depth);
+    let (name, attributes) = if foreign {
+        (tag.name, &normalize_foreign_attributes(tag.attributes, config))
+    } else {
+        (
+            &*normalize_name(tag.name, config),
+            &normalize_attributes(tag.attributes, config),
+        )
+    };
+    let tag = TagParams {
+        name,
+        attributes,
+        ..*tag
+    };
+    if RAW_TEXT_ELEMENTS.contains(&name.to_lowercase().as_str()) {
+        emit_raw_text_element(&tag, children, (depth, config, output));
+    } else if children.is_empty() && (foreign || is_void_element(name)) {
+        let tail = if foreign { " />" } else { void_tag_end(config) };
+        emit_open_tag(&TagParams { tail, ..tag }, &pad, config, output);
+    } else if is_inline_content(children) && !wraps_regardless_of_length(attributes, config) {
+        let inline = format_inline(&tag, children, config);
         if pad.len() + inline.len() <= MAX_LINE_LENGTH {
             output.push_str(&pad);
             output.push_str(&inline);
             output.push('\n');
         } else {
-            emit_open_tag(
-                &TagParams {
-                    name,
-                    attributes,
-                    self_closing: false,
-                },
-                &pad,
-                output,
-            );
-            format_nodes(children, depth + 1, output);
-            output.push_str(&format!("{pad}</{name}>\n"));
+            emit_block_element(&tag, children, (depth, children_scope, output));
         }
     } else {
-        emit_open_tag(
-            &TagParams {
-                name,
-                attributes,
-                self_closing: false,
-            },
-            &pad,
-            output,
-        );
-        format_nodes(children, depth + 1, output);
-        output.push_str(&format!("{pad}</{name}>\n"));
+        emit_block_element(&tag, children, (depth, children_scope, output));
     }
 }
 
-struct PhpDepthState {
+fn emit_block_element(tag: &TagParams, children: &[Node], ctx: (usize, Scope, &mut String)) {
+    let (depth, scope, output) = ctx;
+    let pad = INDENT.repeat(depth);
+    emit_open_tag(tag, &pad, scope.config, output);
+    format_nodes(children, depth + 1, scope, output);
+    push_close_tag(tag, &pad, scope.config, output);
+}
+
+fn prose_piece<'a>(node: &'a Node, config: &Config) -> Option<Piece<'a>> {
+    match node {
+        Node::Text(s) if !s.trim().is_empty() || s.matches('\n').count() <= 1 => Some(Piece::Text(s)),
+        Node::PhpEcho(code) if !is_echo_block_opener(code) && !is_echo_block_closer(code) => {
+            Some(Piece::Atom(format_inline_echo(code, config)))
+        }
+        Node::Element {
+            name,
+            attributes,
+            children,
+            closed: true,
+        } if INLINE_ELEMENTS.contains(&name.to_lowercase().as_str()) && is_inline_content(children) => {
+            let attributes = normalize_attributes(attributes, config);
+            let tag = TagParams {
+                name: &normalize_name(name, config),
+                attributes: &attributes,
+                tail: ">",
+                closed: true,
+            };
+            let atom = format_inline(&tag, children, config);
+            (!wraps_regardless_of_length(&attributes, config)).then_some(Piece::Atom(atom))
+        }
+        _ => None,
+    }
+}
+
+/// Collects the run of text and inline atoms starting at `start` when `prose_wrap` reflows it.
+fn prose_run<'a>(nodes: &'a [Node], start: usize, scope: Scope) -> Option<Vec<Piece<'a>>> {
+    if scope.config.prose_wrap == ProseWrap::Preserve || scope.foreign || scope.preformatted {
+        return None;
+    }
+    let pieces: Vec<Piece> = nodes[start..]
+        .iter()
+        .map_while(|node| prose_piece(node, scope.config))
+        .collect();
+    let has_text = pieces
+        .iter()
+        .any(|piece| matches!(piece, Piece::Text(text) if !text.trim().is_empty()));
+    has_text.then_some(pieces)
+}
+
+struct PhpDepthState<'a> {
     depth: usize,
     switch_stack: Vec<usize>,
+    config: &'a Config,
 }
 
 fn emit_switch_stmt(trimmed: &str, state: &mut PhpDepthState, output: &mut String) {
-    let formatted = format_php_code(trimmed);
+    let formatted = format_php_code(trimmed, state.config);
     let lower = trimmed.to_lowercase();
     if lower.starts_with("switch") && is_php_block_opener(trimmed) {
         let pad = INDENT.repeat(state.depth);
@@ -210,27 +459,29 @@ fn emit_switch_stmt(trimmed: &str, state: &mut PhpDepthState, output: &mut Strin
     }
 }
 
-fn emit_multiline_php(code: &str, pad: &str, depth: &mut usize, output: &mut String) {
+fn emit_multiline_php(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut String) {
+    let config = state.config;
+    let depth = &mut state.depth;
     let is_header = is_header_php_block(code);
     if is_header {
         if let Some((header_code, opener_line)) = split_header_and_opener(code) {
             output.push_str(&format!("{pad}<?php\n"));
-            let reindented = reindent_php_block(&header_code, pad);
+            let reindented = reindent_php_block(&header_code, pad, config);
             output.push_str(&reindented);
             output.push('\n');
             output.push_str(&format!("{pad}?>\n"));
-            let formatted = format_php_code(&opener_line);
+            let formatted = format_php_code(&opener_line, config);
             output.push_str(&format!("{pad}<?php {formatted} ?>\n"));
             *depth += 1;
             return;
         }
         output.push_str(&format!("{pad}<?php\n"));
-        let reindented = reindent_php_block(code, pad);
+        let reindented = reindent_php_block(code, pad, config);
         output.push_str(&reindented);
         output.push('\n');
         output.push_str(&format!("{pad}?>\n"));
     } else {
-        emit_multiline_php_inline(code, pad, output);
+        emit_multiline_php_inline(code, pad, config, output);
     }
     let has_widget_pair = code.contains("::begin(") || code.contains("::end(");
     if has_widget_pair || !is_header {
@@ -242,13 +493,21 @@ fn emit_multiline_php(code: &str, pad: &str, depth: &mut usize, output: &mut Str
     }
 }
 
-fn emit_multiline_php_inline(code: &str, pad: &str, output: &mut String) {
-    let reindented = reindent_php_block(code, pad);
-    let lines: Vec<&str> = reindented.lines().filter(|l| !l.trim().is_empty()).collect();
+fn emit_multiline_php_inline(code: &str, pad: &str, config: &Config, output: &mut String) {
+    let reindented = reindent_php_block(code, pad, config);
+    // Blank lines from the source are kept; the one the reindenter adds after a docblock is not,
+    // so the docblock stays attached to the statement it documents.
+    let all: Vec<&str> = reindented.trim_matches('\n').lines().collect();
+    let lines: Vec<&str> = all
+        .iter()
+        .enumerate()
+        .filter(|&(i, line)| !line.trim().is_empty() || i == 0 || !all[i - 1].trim_end().ends_with("*/"))
+        .map(|(_, line)| *line)
+        .collect();
     if lines.len() > 1 {
         output.push_str(&format!("{pad}<?php {}\n", lines[0].trim_start()));
         for line in &lines[1..lines.len() - 1] {
-            output.push_str(line);
+            output.push_str(line.trim_end());
             output.push('\n');
         }
         output.push_str(&format!("{} ?>\n", lines[lines.len() - 1]));
@@ -258,7 +517,7 @@ fn emit_multiline_php_inline(code: &str, pad: &str, output: &mut String) {
 }
 
 fn emit_single_php(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut String) {
-    let formatted = format_php_code(code);
+    let formatted = format_php_code(code, state.config);
     let lower = code.trim().to_lowercase();
     if lower.starts_with("switch") && is_php_block_opener(code) && !is_php_block_closer(code) {
         let stmt_pad = INDENT.repeat(state.depth);
@@ -291,15 +550,17 @@ fn emit_single_php(code: &str, pad: &str, state: &mut PhpDepthState, output: &mu
             state.depth += 1;
         }
     } else {
-        emit_single_php_long(code, pad, &mut state.depth, output);
+        emit_single_php_long(code, pad, state, output);
     }
 }
 
-fn emit_single_php_long(code: &str, pad: &str, depth: &mut usize, output: &mut String) {
-    let formatted = format_php_code(code);
+fn emit_single_php_long(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut String) {
+    let config = state.config;
+    let depth = &mut state.depth;
+    let formatted = format_php_code(code, config);
     if is_header_php_block(code) {
         output.push_str(&format!("{pad}<?php\n"));
-        let reindented = reindent_php_block(code, pad);
+        let reindented = reindent_php_block(code, pad, config);
         output.push_str(&reindented);
         output.push('\n');
         output.push_str(&format!("{pad}?>\n"));
@@ -321,7 +582,7 @@ fn emit_single_php_long(code: &str, pad: &str, depth: &mut usize, output: &mut S
             "{pad}<?php {condition}\n{inner_pad}? {true_val}\n{inner_pad}: {false_val} ?>\n"
         ));
     } else {
-        let reindented = reindent_php_block(code, pad);
+        let reindented = reindent_php_block(code, pad, config);
         let lines: Vec<&str> = reindented.lines().filter(|l| !l.trim().is_empty()).collect();
         if lines.len() > 1 {
             output.push_str(&format!("{pad}<?php {}\n", lines[0].trim_start()));
@@ -341,15 +602,19 @@ fn emit_single_php_long(code: &str, pad: &str, depth: &mut usize, output: &mut S
     }
 }
 
+/// Applies the rewrites that must see a whole PHP block, since their constructs span lines.
+fn prepare_php_block(code: &str, config: &Config) -> String {
+    if config.short_array_syntax {
+        format_embedded_heredocs(&short_array_syntax(code), config)
+    } else {
+        format_embedded_heredocs(code, config)
+    }
+}
+
 fn emit_php_block(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut String) {
-    let trimmed = code.trim();
-    if let Some(expr) = trimmed.strip_prefix("echo ") {
-        let expr = expr.strip_suffix(';').unwrap_or(expr).trim();
-        let semicolons = count_semicolons_outside_parens(code);
-        if semicolons <= 1 && !expr.contains('\n') {
-            emit_php_echo(expr, pad, state, output);
-            return;
-        }
+    if let Some(expr) = echo_statement(code) {
+        emit_php_echo(expr, pad, state, output);
+        return;
     }
     if is_docblock_only(code) {
         emit_docblock_php(code, pad, output);
@@ -360,7 +625,7 @@ fn emit_php_block(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut
     if is_multiline && has_switch_case(code) {
         emit_php_switch_block(code, state, output);
     } else if is_multiline {
-        emit_multiline_php(code, pad, &mut state.depth, output);
+        emit_multiline_php(code, pad, state, output);
     } else {
         emit_single_php(code, pad, state, output);
     }
@@ -383,8 +648,8 @@ fn emit_php_switch_block(code: &str, state: &mut PhpDepthState, output: &mut Str
         {
             let switch_depth = state.depth;
             let stmt_pad = INDENT.repeat(switch_depth);
-            let switch_stmt = format_php_code(current);
-            let first_case = format_php_code(statements[i + 1]);
+            let switch_stmt = format_php_code(current, state.config);
+            let first_case = format_php_code(statements[i + 1], state.config);
             let case_pad = format!("{stmt_pad}{INDENT}");
             output.push_str(&format!("{stmt_pad}<?php {switch_stmt}\n{case_pad}{first_case} ?>\n"));
             state.switch_stack.push(switch_depth);
@@ -401,41 +666,92 @@ fn emit_php_echo(code: &str, pad: &str, state: &mut PhpDepthState, output: &mut
     if is_echo_block_closer(code) {
         state.depth = state.depth.saturating_sub(1);
         let pad = INDENT.repeat(state.depth);
-        output.push_str(&format_echo(code, &pad));
+        output.push_str(&format_echo(code, &pad, state.config));
     } else {
-        output.push_str(&format_echo(code, pad));
+        output.push_str(&format_echo(code, pad, state.config));
         if is_echo_block_opener(code) {
             state.depth += 1;
         }
     }
 }
 
-fn format_nodes(nodes: &[Node], depth: usize, output: &mut String) {
+/// Blank lines waiting to be written between two siblings; dropped before the first and after the last.
+struct BlankLines {
+    max: usize,
+    pending: usize,
+    started: bool,
+}
+
+impl BlankLines {
+    fn note(&mut self, whitespace: &str) {
+        let blank = whitespace.matches('\n').count().saturating_sub(1);
+        self.pending = self.pending.max(blank.min(self.max));
+    }
+
+    fn flush(&mut self, output: &mut String) {
+        if self.started {
+            output.push_str(&"\n".repeat(self.pending));
+        }
+        self.pending = 0;
+        self.started = true;
+    }
+}
+
+fn format_nodes(nodes: &[Node], depth: usize, scope: Scope, output: &mut String) {
+    let config = scope.config;
     let mut state = PhpDepthState {
         depth,
         switch_stack: Vec::new(),
+        config,
+    };
+    let mut blanks = BlankLines {
+        max: config.max_blank_lines,
+        pending: 0,
+        started: false,
     };
     let mut i = 0usize;
     while i < nodes.len() {
         let pad = INDENT.repeat(state.depth);
 
+        if i > 0
+            && let Node::Text(prev) = &nodes[i - 1]
+        {
+            blanks.note(&prev[prev.trim_end().len()..]);
+        }
+        if let Node::Text(s) = &nodes[i] {
+            let content = s.trim_start();
+            blanks.note(&s[..s.len() - content.len()]);
+            if content.is_empty() {
+                i += 1;
+                continue;
+            }
+        }
+        blanks.flush(output);
+
+        if let Some(pieces) = prose_run(nodes, i, scope) {
+            output.push_str(&reflow(&pieces, &pad, config.prose_wrap));
+            i += pieces.len();
+            continue;
+        }
+
         match &nodes[i] {
             Node::Element {
                 name,
                 attributes,
                 children,
+                closed,
             } => {
-                emit_element(name, attributes, children, (state.depth, output));
-            }
-            Node::Text(s) => {
-                let trimmed = s.trim();
-                if !trimmed.is_empty() {
-                    output.push_str(&format!("{pad}{trimmed}\n"));
-                } else if state.depth <= 1 && s.contains('\n') && s.chars().filter(|&c| c == '\n').count() > 1 {
-                    output.push('\n');
-                }
+                let tag = TagParams {
+                    name,
+                    attributes,
+                    tail: ">",
+                    closed: *closed,
+                };
+                emit_element(&tag, children, (state.depth, scope, output));
             }
+            Node::Text(s) => output.push_str(&format!("{pad}{}\n", s.trim())),
             Node::PhpBlock(code) => {
+                let code = &prepare_php_block(code, config);
                 if state.depth == 0 && (is_header_php_block(code) || is_docblock_only(code)) {
                     let mut merged = code.trim().to_string();
                     let mut j = i + 1;
@@ -452,7 +768,7 @@ fn format_nodes(nodes: &[Node], depth: usize, output: &mut String) {
                                 if !merged.is_empty() {
                                     merged.push('\n');
                                 }
-                                merged.push_str(next_code.trim());
+                                merged.push_str(prepare_php_block(next_code, config).trim());
                                 merged_any = true;
                                 j += 1;
                             }
@@ -472,31 +788,50 @@ fn format_nodes(nodes: &[Node], depth: usize, output: &mut String) {
             Node::Doctype(s) => {
                 output.push_str(&format!("{pad}<!DOCTYPE {s}>\n"));
             }
-            Node::Comment(s) => {
-                output.push_str(&format!("{pad}<!-- {s} -->\n"));
-            }
+            Node::Comment(s) => output.push_str(&format_comment(s, &pad, config)),
+            Node::Verbatim(s) => output.push_str(&format!("{s}\n")),
         }
 
         i += 1;
     }
 }
 
-pub fn format(nodes: &[Node]) -> String {
+pub fn format(nodes: &[Node], config: &Config) -> String {
+    let converted;
+    let nodes = if config.alternative_syntax {
+        converted = alternative_syntax(nodes);
+        &converted
+    } else {
+        nodes
+    };
     let mut output = String::new();
-    format_nodes(nodes, 0, &mut output);
+    format_nodes(nodes, 0, Scope::html(config), &mut output);
     output
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{ChainWrap, EchoStyle, UnclosedTags, VoidStyle};
     use crate::parser::{ast, lexer};
     use pretty_assertions::assert_eq;
 
     fn format_str(input: &str) -> String {
+        format_with(input, &Config::default())
+    }
+
+    fn format_with(input: &str, config: &Config) -> String {
         let tokens = lexer::tokenize(input);
         let nodes = ast::parse(tokens);
-        format(&nodes)
+        format(&nodes, config)
+    }
+
+    fn wrap_config(wrap_attributes: WrapAttributes, bracket_same_line: bool) -> Config {
+        Config {
+            wrap_attributes,
+            bracket_same_line,
+            ..Config::default()
+        }
     }
 
     #[test]
@@ -564,4 +899,281 @@ mod tests {
 ";
         assert_eq!(format_str(input), expected);
     }
+
+    #[test]
+    fn template_script_with_foreign_tags_kept_verbatim() {
+        let input = "<script type=\"text/x-template\">\n    <li><%= name %></li>\n</script>";
+        let expected = "<script type=\"text/x-template\">\n    <li><%= name %></li>\n</script>\n";
+        assert_eq!(format_str(input), expected);
+    }
+
+    #[test]
+    fn force_expand_wraps_short_tag() {
+        let config = wrap_config(WrapAttributes::ForceExpandMultiline, false);
+        let input = "<a href=\"/\" class=\"btn\">Home</a><br class=\"x\">";
+        let expected = "<a\n    href=\"/\"\n    class=\"btn\"\n>\n    Home\n</a>\n<br class=\"x\" />\n";
+        assert_eq!(format_with(input, &config), expected);
+    }
+
+    #[test]
+    fn preserve_keeps_source_breaks() {
+        let config = wrap_config(WrapAttributes::Preserve, true);
+        let input = "<div class=\"a\" id=\"b\"\n  data-x=\"1\"\n   data-y=\"2\"><p>x</p></div>";
+        let expected = "<div class=\"a\" id=\"b\"\n    data-x=\"1\"\n    data-y=\"2\">\n    <p>x</p>\n</div>\n";
+        assert_eq!(format_with(input, &config), expected);
+    }
+
+    #[test]
+    fn aligned_attributes_under_first() {
+        let config = wrap_config(WrapAttributes::Aligned, true);
+        let long = "x".repeat(100);
+        let input = format!("<input type=\"text\" name=\"q\" placeholder=\"{long}\">");
+        let expected = format!("<input type=\"text\"\n       name=\"q\"\n       placeholder=\"{long}\" />\n");
+        assert_eq!(format_with(&input, &config), expected);
+    }
+
+    #[test]
+    fn aligned_class_list_wraps_at_alignment_column() {
+        let config = wrap_config(WrapAttributes::Aligned, true);
+        let classes = "d-flex flex-column flex-md-row justify-content-between align-items-start align-items-md-center \
+                       gap-2 gap-md-3 px-3 py-2 mb-3 border-bottom bg-light shadow-sm rounded-top";
+        let input = format!("<div class=\"{classes}\" id=\"toolbar\"><p>x</p></div>");
+        let expected = "<div class=\"d-flex flex-column flex-md-row justify-content-between align-items-start \
+                        align-items-md-center gap-2\n         gap-md-3 px-3 py-2 mb-3 border-bottom bg-light shadow-sm \
+                        rounded-top\"\n     id=\"toolbar\">\n    <p>x</p>\n</div>\n";
+        let output = format_with(&input, &config);
+        assert_eq!(output, expected);
+        assert_eq!(format_with(&output, &config), expected);
+    }
+
+    #[test]
+    fn legacy_markup_normalized_when_enabled() {
+        let config = Config {
+            lowercase_names: true,
+            bare_boolean_attributes: true,
+            void_style: VoidStyle::Bare,
+            ..Config::default()
+        };
+        let input = "<DIV><BR/><Input DISABLED=\"disabled\"><br /></DIV>";
+        let expected = "<div>\n    <br>\n    <input disabled>\n    <br>\n</div>\n";
+        assert_eq!(format_with(input, &config), expected);
+    }
+
+    #[test]
+    fn svg_keeps_case_and_self_closes_empty_elements() {
+        let config = Config {
+            lowercase_names: true,
+            ..Config::default()
+        };
+        let input = "<DIV><svg viewBox=\"0 0 8 8\"><clipPath ID=\"c\"></clipPath><Path d=\"M0 0\"/></svg></DIV>";
+        let expected = "\
+<div>
+    <svg viewBox=\"0 0 8 8\">
+        <clipPath ID=\"c\" />
+        <Path d=\"M0 0\" />
+    </svg>
+</div>
+";
+        assert_eq!(format_with(input, &config), expected);
+    }
+
+    #[test]
+    fn prose_reflowed_around_inline_atoms() {
+        let config = Config {
+            prose_wrap: ProseWrap::Never,
+            ..Config::default()
+        };
+        let input =
+            "<div>\n  <b>Note:</b> read\n      the <a href=\"#\">guide</a>,\n  then <?= $next ?>.\n<p>x</p></div>";
+        let expected =
+            "<div>\n    <b>Note:</b> read the <a href=\"#\">guide</a>, then <?= $next ?>.\n    <p>x</p>\n</div>\n";
+        assert_eq!(format_with(input, &config), expected);
+    }
+
+    #[test]
+    fn blank_lines_kept_between_siblings_at_any_depth() {
+        let input = "<div>\n\n<ul>\n<li>a</li>\n\n\n\n<li>b</li>\n<?php $x = 1; ?>\n\n<li>c</li>\n\n</ul>\n\n</div>";
+        let expected = "<div>\n    <ul>\n        <li>a</li>\n\n        <li>b</li>\n        <?php $x = 1; ?>\n\n        <li>c</li>\n    </ul>\n</div>\n";
+        assert_eq!(format_str(input), expected);
+        let config = Config {
+            max_blank_lines: 0,
+            ..Config::default()
+        };
+        let flat = "<div>\n    <ul>\n        <li>a</li>\n        <li>b</li>\n        <?php $x = 1; ?>\n        <li>c</li>\n    </ul>\n</div>\n";
+        assert_eq!(format_with(input, &config), flat);
+    }
+
+    #[test]
+    fn blank_lines_limited_inside_php_blocks() {
+        let input = "<div>\n<?php\n$a = 1;\n\n\n\n$b = 2;\n\n$c = 3;\n?>\n</div>";
+        let limit = |max_blank_lines| Config {
+            max_blank_lines,
+            ..Config::default()
+        };
+        assert_eq!(
+            format_str(input),
+            "<div>\n    <?php $a = 1;\n\n    $b = 2;\n\n    $c = 3; ?>\n</div>\n"
+        );
+        assert_eq!(
+            format_with(input, &limit(0)),
+            "<div>\n    <?php $a = 1;\n    $b = 2;\n    $c = 3; ?>\n</div>\n"
+        );
+        assert_eq!(
+            format_with(input, &limit(2)),
+            "<div>\n    <?php $a = 1;\n\n\n    $b = 2;\n\n    $c = 3; ?>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn ignored_node_printed_as_written() {
+        let input = "<div>\n<!-- phew-ignore -->\n<pre-like   a=1>\n x  </pre-like>\n<?php // phew-ignore ?>\n<?php $a=[1,2]; ?>\n<p>  y  </p></div>";
+        let expected = "<div>\n    <!-- phew-ignore -->\n<pre-like   a=1>\n x  </pre-like>\n    <?php // phew-ignore ?>\n<?php $a=[1,2]; ?>\n    <p>y</p>\n</div>\n";
+        assert_eq!(format_str(input), expected);
+    }
+
+    #[test]
+    fn ignore_directives_never_take_a_parent_end_tag() {
+        assert_eq!(
+            format_str("<div><p>a</p><!-- phew-ignore --></div>"),
+            "<div>\n    <p>a</p>\n    <!-- phew-ignore -->\n</div>\n"
+        );
+        let region = "<section>\n<div>\n<!-- phew-ignore-start -->\n<b>x</b>\n</div>\n<!-- phew-ignore-end -->\n<p>y</p>\n</section>";
+        let expected = "<section>\n    <div>\n        <!-- phew-ignore-start -->\n        <b>x</b>\n    </div>\n    \
+                        <!-- phew-ignore-end -->\n    <p>y</p>\n</section>\n";
+        assert_eq!(format_str(region), expected);
+    }
+
+    #[test]
+    fn ignored_node_keeps_its_own_indentation() {
+        let indented = "<div>\n  <!-- phew-ignore -->\n      <span   a=\"1\">x</span>\n</div>\n";
+        assert_eq!(
+            format_str(indented),
+            "<div>\n    <!-- phew-ignore -->\n      <span   a=\"1\">x</span>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn unclosed_elements_preserved_or_inserted() {
+        let input = "<ul><li>One<li><b>Two</b></ul><div>";
+        let preserve = Config {
+            unclosed_tags: UnclosedTags::Preserve,
+            ..Config::default()
+        };
+        let preserved = "<ul>\n    <li>\n        One\n        <li>\n            <b>Two</b>\n</ul>\n<div>\n";
+        let inserted = "<ul>\n    <li>\n        One\n        <li>\n            <b>Two</b>\n        </li>\n    </li>\n</ul>\n<div></div>\n";
+        assert_eq!(format_with(input, &preserve), preserved);
+        assert_eq!(format_str(input), inserted);
+    }
+
+    #[test]
+    fn echo_style_short_and_long() {
+        let input =
+            "<?php echo $a; ?>\n<?php print $b; // note ?>\n<?= $c; ?>\n<?php echo $d,\n    $e; ?>\n<p><?= $f; ?></p>";
+        let long = Config {
+            echo_style: EchoStyle::Long,
+            ..Config::default()
+        };
+        assert_eq!(
+            format_str(input),
+            "<?= $a ?>\n<?= $b // note ?>\n<?= $c ?>\n<?= $d, $e ?>\n<p><?= $f ?></p>\n"
+        );
+        assert_eq!(
+            format_with(input, &long),
+            "<?php echo $a; ?>\n<?php echo $b; // note ?>\n<?php echo $c; ?>\n<?php echo $d, $e; ?>\n<p><?php echo $f; ?></p>\n"
+        );
+    }
+
+    #[test]
+    fn parenthesized_echo_and_print_converted() {
+        let input = "<?php echo($x) ?>\n<?php print($z); ?>\n<?php print_r($y); ?>";
+        let long = Config {
+            echo_style: EchoStyle::Long,
+            ..Config::default()
+        };
+        assert_eq!(format_str(input), "<?= ($x) ?>\n<?= ($z) ?>\n<?php print_r($y); ?>\n");
+        assert_eq!(
+            format_with("<?= ($x) ?>\n<?php print($z) ?>", &long),
+            "<?php echo ($x); ?>\n<?php echo ($z); ?>\n"
+        );
+    }
+
+    #[test]
+    fn commented_echo_lines_indented_one_level_per_line() {
+        let input = "<?= implode(', ', [ // items\n$a,\nfoo($b, [\n1, // one\n2,\n]),\n]) ?>";
+        let expected =
+            "<?= implode(', ', [ // items\n    $a,\n    foo($b, [\n        1, // one\n        2,\n    ]),\n]) ?>\n";
+        assert_eq!(format_str(input), expected);
+    }
+
+    #[test]
+    fn heredoc_bodies_move_with_closing_marker() {
+        let input = "<div>\n<?php\nif ($a) {\n$sql = <<<SQL\nSELECT *\n  FROM t\nSQL;\n$raw = <<<'TXT'\n    a\n  b\n    TXT;\n}\n?>\n</div>";
+        let reindent = Config {
+            reindent_heredocs: true,
+            ..Config::default()
+        };
+        let old_php = Config {
+            php_version: "7.2".into(),
+            ..reindent.clone()
+        };
+        let moved = "<div>\n    <?php if ($a) {\n        $sql = <<<SQL\n        SELECT *\n          FROM t\n        SQL;\n        \
+                     $raw = <<<'TXT'\n    a\n  b\n    TXT;\n    } ?>\n</div>\n";
+        assert_eq!(format_with(input, &reindent), moved);
+        assert_eq!(format_with(input, &old_php), format_str(input));
+    }
+
+    #[test]
+    fn chain_split_policy_configurable() {
+        let input = "<?= $form->field($model, 'username')->textInput(['maxlength' => true, 'autofocus' => true])\
+                     ->label(false)->hint('Your login name') ?>";
+        let fill = Config {
+            chain_wrap: ChainWrap::Fill,
+            ..Config::default()
+        };
+        let receiver_only = Config {
+            chain_first_call_inline: false,
+            ..Config::default()
+        };
+        let min_calls = Config {
+            chain_min_calls: 5,
+            ..Config::default()
+        };
+        assert_eq!(
+            format_str(input),
+            "<?= $form->field($model, 'username')\n    ->textInput(['maxlength' => true, 'autofocus' => true])\n    \
+             ->label(false)\n    ->hint('Your login name') ?>\n"
+        );
+        assert_eq!(
+            format_with(input, &fill),
+            "<?= $form->field($model, 'username')->textInput(['maxlength' => true, 'autofocus' => true])->label(false)\n    \
+             ->hint('Your login name') ?>\n"
+        );
+        assert_eq!(
+            format_with(input, &receiver_only),
+            "<?= $form\n    ->field($model, 'username')\n    ->textInput(['maxlength' => true, 'autofocus' => true])\n    \
+             ->label(false)\n    ->hint('Your login name') ?>\n"
+        );
+        assert!(!format_with(input, &min_calls).contains("\n    ->"));
+    }
+
+    #[test]
+    fn values_with_double_quotes_single_quoted() {
+        let input = "<div data-x='{\"a\":1}' title='say \"hi\"' data-y=\"it's\" data-z='<?= \"a\" ?>'></div>";
+        assert_eq!(
+            format_str(input),
+            "<div data-x='{\"a\":1}' title='say \"hi\"' data-y=\"it's\" data-z=\"<?= \"a\" ?>\"></div>\n"
+        );
+    }
+
+    #[test]
+    fn commented_brace_blocks_indented_and_rewritten() {
+        let input = "<div>\n<?php $f = function () { return 1; }; ?>\n<?php if ($a) { // check ?>\n<p>a</p>\n<?php } // done ?>\n</div>";
+        let config = Config {
+            alternative_syntax: true,
+            ..Config::default()
+        };
+        let body = "    <?php if ($a): // check ?>\n        <p>a</p>\n    <?php endif; // done ?>\n</div>\n";
+        assert!(format_with(input, &config).ends_with(body));
+        assert!(format_str(input).ends_with(&body.replace("): ", ") { ").replace("endif;", "}")));
+    }
 }
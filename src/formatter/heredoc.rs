@@ -0,0 +1,210 @@
+use super::css::format_css;
+use super::indent::{INDENT, detect_heredoc, is_heredoc_closing};
+use super::js::reindent_js;
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Js,
+    Css,
+}
+
+#[derive(Debug, PartialEq)]
+struct Opener {
+    marker: String,
+    language: Language,
+    nowdoc: bool,
+}
+
+fn called_method(before: &str) -> Option<&str> {
+    let before = before.trim_end().strip_suffix('(')?.trim_end();
+    let start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |pos| pos + 1);
+    let name = &before[start..];
+    (!name.is_empty()).then_some(name)
+}
+
+fn marker_language(marker: &str, config: &Config) -> Option<Language> {
+    match marker.to_uppercase().as_str() {
+        "JS" | "JAVASCRIPT" if config.format_js => Some(Language::Js),
+        "CSS" if config.format_css => Some(Language::Css),
+        _ => None,
+    }
+}
+
+fn embedded_opener(line: &str, config: &Config) -> Option<Opener> {
+    let pos = line.find("<<<")?;
+    let method = called_method(&line[..pos])?;
+    if !config.heredoc_methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+        return None;
+    }
+    let marker = detect_heredoc(line)?;
+    Some(Opener {
+        language: marker_language(&marker, config)?,
+        nowdoc: line[pos + 3..].trim_start().starts_with('\''),
+        marker,
+    })
+}
+
+fn find_brace_end(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, &ch) in chars.iter().enumerate().skip(open) {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn placeholder(index: usize) -> String {
+    format!("__PHEW_HEREDOC_{index}__")
+}
+
+/// Replaces `{$expr}` and `${expr}` interpolations with identifier-like placeholders.
+fn mask_interpolations(body: &str) -> (String, Vec<String>) {
+    let chars: Vec<char> = body.chars().collect();
+    let mut masked = String::new();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        let open = match (chars[i], next) {
+            ('{', Some('$')) => Some(i),
+            ('$', Some('{')) => Some(i + 1),
+            _ => None,
+        };
+        if let Some(end) = open.and_then(|open| find_brace_end(&chars, open)) {
+            masked.push_str(&placeholder(segments.len()));
+            segments.push(chars[i..=end].iter().collect());
+            i = end + 1;
+            continue;
+        }
+        masked.push(chars[i]);
+        i += 1;
+    }
+    (masked, segments)
+}
+
+fn unmask_interpolations(formatted: &str, segments: &[String]) -> Option<String> {
+    let mut out = formatted.to_string();
+    for (index, segment) in segments.iter().enumerate() {
+        let key = placeholder(index);
+        if out.matches(&key).count() != 1 {
+            return None;
+        }
+        out = out.replace(&key, segment);
+    }
+    Some(out)
+}
+
+fn format_body(body: &str, opener: &Opener, pad: &str) -> Option<String> {
+    let (source, segments) = if opener.nowdoc {
+        (body.to_string(), Vec::new())
+    } else {
+        mask_interpolations(body)
+    };
+    let formatted = match opener.language {
+        Language::Js => reindent_js(&source, pad)?,
+        Language::Css => format_css(&source, pad)?,
+    };
+    if formatted.trim().is_empty() {
+        return None;
+    }
+    unmask_interpolations(formatted.trim_end_matches('\n'), &segments)
+}
+
+/// Formats `<<<JS` / `<<<CSS` heredoc and nowdoc arguments of the configured methods,
+/// e.g. `$this->registerJs(<<<JS ... JS)`, as embedded JavaScript or CSS.
+///
+/// The body is indented one level deeper than the closing marker, so PHP 7.3 flexible
+/// heredocs stay valid. Bodies the embedded formatter rejects are kept as is.
+pub fn format_embedded_heredocs(code: &str, config: &Config) -> String {
+    if !code.contains("<<<") {
+        return code.to_string();
+    }
+    let lines: Vec<&str> = code.split('\n').collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        out.push(lines[i].to_string());
+        let Some(opener) = embedded_opener(lines[i], config) else {
+            i += 1;
+            continue;
+        };
+        let Some(close) = (i + 1..lines.len()).find(|&j| is_heredoc_closing(lines[j], &opener.marker)) else {
+            i += 1;
+            continue;
+        };
+        let closing = lines[close];
+        let pad = format!("{}{INDENT}", &closing[..closing.len() - closing.trim_start().len()]);
+        let body = lines[i + 1..close].join("\n");
+        match format_body(&body, &opener, &pad) {
+            Some(formatted) => out.push(formatted),
+            None => out.extend(lines[i + 1..close].iter().map(ToString::to_string)),
+        }
+        out.push(closing.to_string());
+        i = close + 1;
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn register_js_body_reindented() {
+        let input = "$this->registerJs(<<<JS\n$(function() {\n      init();\n  });\nJS, View::POS_READY);";
+        let expected = "$this->registerJs(<<<JS\n    $(function() {\n        init();\n    });\nJS, View::POS_READY);";
+        assert_eq!(format_embedded_heredocs(input, &Config::default()), expected);
+    }
+
+    #[test]
+    fn register_css_follows_closing_marker_indent() {
+        let input = "    $this->registerCss(<<<'CSS'\n.a{color:red}\n    CSS);";
+        let expected = "    $this->registerCss(<<<'CSS'\n        .a {\n            color: red;\n        }\n    CSS);";
+        assert_eq!(format_embedded_heredocs(input, &Config::default()), expected);
+    }
+
+    #[test]
+    fn interpolation_kept_intact() {
+        let input = "$this->registerJs(<<<JS\nif (a) {\ngo({$options['id']}, \"${url}\");\n}\nJS);";
+        let expected = "$this->registerJs(<<<JS\n    if (a) {\n        go({$options['id']}, \"${url}\");\n    }\nJS);";
+        assert_eq!(format_embedded_heredocs(input, &Config::default()), expected);
+    }
+
+    #[test]
+    fn unknown_method_or_marker_untouched() {
+        let config = Config::default();
+        let other_method = "$view->render(<<<JS\nif (a) {\ngo();\n}\nJS);";
+        let other_marker = "$this->registerJs(<<<EOT\nif (a) {\ngo();\n}\nEOT);";
+        assert_eq!(format_embedded_heredocs(other_method, &config), other_method);
+        assert_eq!(format_embedded_heredocs(other_marker, &config), other_marker);
+    }
+
+    #[test]
+    fn configured_method_formatted() {
+        let config = Config {
+            heredoc_methods: vec!["addScript".into()],
+            ..Config::default()
+        };
+        let input = "$page->addScript(<<<JS\nif (a) {\ngo();\n}\nJS);";
+        let expected = "$page->addScript(<<<JS\n    if (a) {\n        go();\n    }\nJS);";
+        assert_eq!(format_embedded_heredocs(input, &config), expected);
+    }
+
+    #[test]
+    fn unbalanced_body_untouched() {
+        let input = "$this->registerJs(<<<JS\nif (a) {\nJS);";
+        assert_eq!(format_embedded_heredocs(input, &Config::default()), input);
+    }
+}
@@ -1,5 +1,407 @@
+use super::css::format_style_attribute;
+use super::indent::MAX_LINE_LENGTH;
+use super::json::compact_json;
+use crate::config::{ClassSort, Config, VoidStyle};
+use crate::parser::lexer::Attribute;
+
+const TEMPLATE_MARKERS: &[&str] = &["<?", "{{", "{%", "<%", "${"];
+
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+];
+
+const FOREIGN_CAMEL_CASE_NAMES: &[&str] = &[
+    "attributeName",
+    "baseProfile",
+    "clipPath",
+    "clipPathUnits",
+    "definitionURL",
+    "feBlend",
+    "feColorMatrix",
+    "feComposite",
+    "feDropShadow",
+    "feFlood",
+    "feGaussianBlur",
+    "feMerge",
+    "feMergeNode",
+    "feOffset",
+    "foreignObject",
+    "gradientTransform",
+    "gradientUnits",
+    "linearGradient",
+    "markerHeight",
+    "markerUnits",
+    "markerWidth",
+    "maskContentUnits",
+    "maskUnits",
+    "pathLength",
+    "patternContentUnits",
+    "patternTransform",
+    "patternUnits",
+    "preserveAspectRatio",
+    "radialGradient",
+    "refX",
+    "refY",
+    "spreadMethod",
+    "startOffset",
+    "stdDeviation",
+    "textLength",
+    "textPath",
+    "viewBox",
+];
+
+fn rewrite_value(attr: &Attribute, rewrite: impl Fn(&str) -> Option<String>) -> Attribute {
+    let value = attr.value.as_deref().and_then(rewrite);
+    Attribute {
+        value: value.or_else(|| attr.value.clone()),
+        ..attr.clone()
+    }
+}
+
+/// Lowercases an HTML tag or attribute name when `lowercase_names` is set.
+///
+/// SVG/MathML camelCase names and PHP segments inside the tag are kept as written.
+pub fn normalize_name(name: &str, config: &Config) -> String {
+    let foreign = FOREIGN_CAMEL_CASE_NAMES.iter().any(|n| n.eq_ignore_ascii_case(name));
+    if !config.lowercase_names || foreign || has_template_syntax(name) {
+        return name.to_string();
+    }
+    name.to_lowercase()
+}
+
+/// Closing of a void element tag for the configured `void_style`.
+pub fn void_tag_end(config: &Config) -> &'static str {
+    match config.void_style {
+        VoidStyle::SelfClosing => " />",
+        VoidStyle::Bare => ">",
+    }
+}
+
+fn is_redundant_boolean_value(attr: &Attribute) -> bool {
+    BOOLEAN_ATTRIBUTES.contains(&attr.name.to_lowercase().as_str())
+        && attr
+            .value
+            .as_deref()
+            .is_some_and(|v| v.is_empty() || v.eq_ignore_ascii_case(&attr.name))
+}
+
+fn is_json_attribute(name: &str, config: &Config) -> bool {
+    config.json_attributes.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn has_template_syntax(value: &str) -> bool {
+    TEMPLATE_MARKERS.iter().any(|marker| value.contains(marker))
+}
+
+fn matches_group(name: &str, group: &str) -> bool {
+    group.split('|').any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    })
+}
+
+/// Index of the first group matching `name`; a bare `*` group catches everything else,
+/// otherwise unmatched names go after all groups.
+fn group_index(name: &str, groups: &[String]) -> usize {
+    groups
+        .iter()
+        .position(|group| group != "*" && matches_group(name, group))
+        .or_else(|| groups.iter().position(|group| group == "*"))
+        .unwrap_or(groups.len())
+}
+
+fn is_php_attribute(attr: &Attribute) -> bool {
+    attr.name.starts_with("<?")
+}
+
+/// Stable-sorts attributes by `attribute_groups` within each run between PHP-in-tag segments.
+fn sort_attributes(attributes: &mut [Attribute], groups: &[String]) {
+    for run in attributes.split_mut(is_php_attribute) {
+        run.sort_by_cached_key(|attr| group_index(&attr.name.to_lowercase(), groups));
+    }
+}
+
+/// Collapses whitespace in a `class` value, drops duplicate classes and applies `class_sort`.
+///
+/// Returns `None` for values with PHP or template syntax so they stay untouched.
+pub fn normalize_class(value: &str, config: &Config) -> Option<String> {
+    if has_template_syntax(value) {
+        return None;
+    }
+    let mut classes: Vec<&str> = Vec::new();
+    for class in value.split_whitespace() {
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    match config.class_sort {
+        ClassSort::None => {}
+        ClassSort::Alphabetical => classes.sort_unstable(),
+        ClassSort::Groups => classes.sort_by_key(|class| group_index(class, &config.class_groups)),
+    }
+    Some(classes.join(" "))
+}
+
+/// Breaks a class list whose value starts at column `start` so no line exceeds `MAX_LINE_LENGTH`.
+///
+/// Continuation lines are prefixed with `pad`. Returns `None` when the list fits or holds template syntax.
+pub fn wrap_class_list(value: &str, start: usize, pad: &str) -> Option<String> {
+    if has_template_syntax(value) {
+        return None;
+    }
+    let mut out = String::new();
+    let mut width = start;
+    for class in value.split_whitespace() {
+        if !out.is_empty() && width + class.len() + 2 > MAX_LINE_LENGTH {
+            out.push('\n');
+            out.push_str(pad);
+            width = pad.len();
+        } else if !out.is_empty() {
+            out.push(' ');
+            width += 1;
+        }
+        out.push_str(class);
+        width += class.len();
+    }
+    out.contains('\n').then_some(out)
+}
+
+pub fn attribute_value<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attr| attr.name.eq_ignore_ascii_case(name))
+        .and_then(|attr| attr.value.as_deref())
+}
+
+fn normalize_attribute(attr: &Attribute, config: &Config, foreign: bool) -> Attribute {
+    let attr = Attribute {
+        name: if foreign {
+            attr.name.clone()
+        } else {
+            normalize_name(&attr.name, config)
+        },
+        ..attr.clone()
+    };
+    if config.bare_boolean_attributes && !foreign && is_redundant_boolean_value(&attr) {
+        Attribute { value: None, ..attr }
+    } else if config.normalize_class && attr.name.eq_ignore_ascii_case("class") {
+        rewrite_value(&attr, |value| normalize_class(value, config))
+    } else if config.format_style_attribute && attr.name.eq_ignore_ascii_case("style") {
+        rewrite_value(&attr, format_style_attribute)
+    } else if is_json_attribute(&attr.name, config) {
+        rewrite_value(&attr, compact_json)
+    } else {
+        attr
+    }
+}
+
+/// Applies the configured attribute rules to a tag's attribute list.
+pub fn normalize_attributes(attributes: &[Attribute], config: &Config) -> Vec<Attribute> {
+    normalize_attribute_list(attributes, config, false)
+}
+
+/// Applies the attribute rules inside SVG/MathML, where names keep their case and HTML boolean
+/// attributes do not apply.
+pub fn normalize_foreign_attributes(attributes: &[Attribute], config: &Config) -> Vec<Attribute> {
+    normalize_attribute_list(attributes, config, true)
+}
+
+fn normalize_attribute_list(attributes: &[Attribute], config: &Config, foreign: bool) -> Vec<Attribute> {
+    let mut normalized: Vec<Attribute> = attributes
+        .iter()
+        .map(|attr| normalize_attribute(attr, config, foreign))
+        .collect();
+    if config.sort_attributes {
+        sort_attributes(&mut normalized, &config.attribute_groups);
+    }
+    normalized
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn attr(name: &str, value: Option<&str>) -> Attribute {
+        Attribute {
+            name: name.into(),
+            value: value.map(Into::into),
+            line_break: false,
+        }
+    }
+
+    #[test]
+    fn style_untouched_by_default() {
+        let attrs = vec![attr("style", Some("color:red"))];
+        assert_eq!(normalize_attributes(&attrs, &Config::default()), attrs);
+    }
+
+    #[test]
+    fn style_normalized_when_enabled() {
+        let config = Config {
+            format_style_attribute: true,
+            ..Config::default()
+        };
+        let attrs = vec![attr("class", Some("a")), attr("style", Some("color:red;margin:0;"))];
+        assert_eq!(
+            normalize_attributes(&attrs, &config),
+            vec![attr("class", Some("a")), attr("style", Some("color: red; margin: 0"))]
+        );
+    }
+
+    #[test]
+    fn json_attribute_compacted_when_listed() {
+        let config = Config {
+            json_attributes: vec!["data-options".into()],
+            ..Config::default()
+        };
+        let attrs = vec![
+            attr("data-options", Some("{ \"a\": [1, 2] }")),
+            attr("data-other", Some("{ \"a\": 1 }")),
+        ];
+        assert_eq!(
+            normalize_attributes(&attrs, &config),
+            vec![
+                attr("data-options", Some("{\"a\":[1,2]}")),
+                attr("data-other", Some("{ \"a\": 1 }")),
+            ]
+        );
+    }
+
+    #[test]
+    fn class_whitespace_and_duplicates_collapsed() {
+        let attrs = vec![attr("class", Some(" btn  btn-primary btn\n pull-right "))];
+        assert_eq!(
+            normalize_attributes(&attrs, &Config::default()),
+            vec![attr("class", Some("btn btn-primary pull-right"))]
+        );
+    }
+
+    #[test]
+    fn class_with_php_untouched() {
+        let value = "btn  <?= $active ? 'active' : '' ?>";
+        assert_eq!(normalize_class(value, &Config::default()), None);
+        assert_eq!(normalize_class("a  {{ cls }}", &Config::default()), None);
+    }
+
+    #[test]
+    fn class_sorted_by_groups() {
+        let config = Config {
+            class_sort: ClassSort::Groups,
+            class_groups: vec!["row".into(), "col-*".into(), "m*".into(), "text-*".into()],
+            ..Config::default()
+        };
+        assert_eq!(
+            normalize_class("text-muted card mt-2 col-md-6 row", &config).unwrap(),
+            "row col-md-6 mt-2 text-muted card"
+        );
+        let config = Config {
+            class_sort: ClassSort::Alphabetical,
+            ..Config::default()
+        };
+        assert_eq!(normalize_class("b c a", &config).unwrap(), "a b c");
+    }
+
+    #[test]
+    fn long_class_list_wrapped() {
+        let value = "alpha0 alpha1 alpha2 alpha3 alpha4 alpha5 alpha6 alpha7";
+        assert_eq!(
+            wrap_class_list(value, 90, "  ").unwrap(),
+            "alpha0 alpha1 alpha2 alpha3\n  alpha4 alpha5 alpha6 alpha7"
+        );
+        assert_eq!(wrap_class_list("a b", 10, "  "), None);
+    }
+
+    #[test]
+    fn attributes_untouched_order_by_default() {
+        let attrs = vec![attr("href", Some("/")), attr("id", Some("a"))];
+        assert_eq!(normalize_attributes(&attrs, &Config::default()), attrs);
+    }
+
+    #[test]
+    fn attributes_sorted_by_groups_within_php_runs() {
+        let config = Config {
+            sort_attributes: true,
+            ..Config::default()
+        };
+        let attrs = vec![
+            attr("onclick", Some("go()")),
+            attr("data-id", Some("1")),
+            attr("title", Some("t")),
+            attr("CLASS", Some("a")),
+            attr("<?= $extra ?>", None),
+            attr("aria-label", Some("x")),
+            attr("src", Some("/a.png")),
+            attr("id", Some("b")),
+        ];
+        let names: Vec<String> = normalize_attributes(&attrs, &config)
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "CLASS",
+                "data-id",
+                "onclick",
+                "title",
+                "<?= $extra ?>",
+                "id",
+                "src",
+                "aria-label"
+            ]
+        );
+    }
+
     #[test]
-    fn it_loads() {}
+    fn names_lowercased_and_booleans_bare_when_enabled() {
+        let config = Config {
+            lowercase_names: true,
+            bare_boolean_attributes: true,
+            ..Config::default()
+        };
+        let attrs = vec![
+            attr("DISABLED", Some("disabled")),
+            attr("Checked", Some("")),
+            attr("viewBox", Some("0 0 24 24")),
+            attr("Value", Some("disabled")),
+            attr("<?= $X ?>", None),
+        ];
+        assert_eq!(
+            normalize_attributes(&attrs, &config),
+            vec![
+                attr("disabled", None),
+                attr("checked", None),
+                attr("viewBox", Some("0 0 24 24")),
+                attr("value", Some("disabled")),
+                attr("<?= $X ?>", None),
+            ]
+        );
+        assert_eq!(normalize_name("DIV", &config), "div");
+        assert_eq!(normalize_name("linearGradient", &config), "linearGradient");
+    }
 }
@@ -1,6 +1,7 @@
 use super::docblock::{extract_docblock_body, flush_docblocks, merge_descriptions_and_vars};
-use super::php::format_php_code;
+use super::php::{format_php_code, split_trailing_comment};
 use super::split::try_split_long_line;
+use crate::config::Config;
 
 pub const INDENT: &str = "    ";
 pub const MAX_LINE_LENGTH: usize = 120;
@@ -41,7 +42,7 @@ pub fn has_switch_case(code: &str) -> bool {
 }
 
 pub fn is_php_block_opener(code: &str) -> bool {
-    let trimmed = code.trim();
+    let (trimmed, _) = split_trailing_comment(code);
     trimmed.ends_with(':') || trimmed.ends_with('{') || trimmed.contains("::begin(")
 }
 
@@ -464,6 +465,13 @@ pub fn detect_heredoc(line: &str) -> Option<String> {
     }
 }
 
+/// Matches a heredoc closing line, allowing PHP 7.3 indentation and trailing code like `JS, View::POS_READY);`.
+pub fn is_heredoc_closing(line: &str, marker: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(marker)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
 pub fn count_semicolons_outside_parens(code: &str) -> usize {
     let mut count = 0;
     let mut depth = 0i32;
@@ -494,8 +502,47 @@ pub fn count_semicolons_outside_parens(code: &str) -> usize {
     count
 }
 
+/// Reports whether a `/* ... */` comment is still open at the end of `line`.
+fn ends_inside_block_comment(line: &str, open: bool) -> bool {
+    if !open && (line.starts_with("//") || (line.starts_with('#') && !line.starts_with("#["))) {
+        return false;
+    }
+    let last_open = line.rfind("/*");
+    let last_close = line.rfind("*/");
+    match (last_open, last_close) {
+        (Some(o), Some(c)) => o > c,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => open,
+    }
+}
+
+/// Writes a heredoc or nowdoc body and its closing line moved to `indent`; PHP 7.3+ strips the
+/// closing marker's indentation from every line. A body line indented less than the marker is
+/// rejected by PHP as written, so such bodies are kept untouched.
+fn push_heredoc(body: &[&str], closing: &str, indent: &str, result: &mut String) {
+    let marker_indent = &closing[..closing.len() - closing.trim_start().len()];
+    let aligned = body
+        .iter()
+        .all(|line| line.trim().is_empty() || line.starts_with(marker_indent));
+    for line in body {
+        if !aligned {
+            result.push_str(line);
+        } else if let Some(rest) = line.strip_prefix(marker_indent) {
+            if !rest.is_empty() {
+                result.push_str(indent);
+                result.push_str(rest);
+            }
+        }
+        result.push('\n');
+    }
+    result.push_str(if aligned { indent } else { marker_indent });
+    result.push_str(closing.trim_start());
+    result.push('\n');
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn reindent_php_block(code: &str, pad: &str) -> String {
+pub fn reindent_php_block(code: &str, pad: &str, config: &Config) -> String {
     let needs_normalize = !code.contains('\n') && (code.contains(';') || has_switch_case(code));
     let code = if needs_normalize {
         normalize_statements(code)
@@ -505,6 +552,7 @@ pub fn reindent_php_block(code: &str, pad: &str) -> String {
     let mut result = String::new();
     let mut depth: i32 = 0;
     let mut prev_blank = false;
+    let mut blank_run = 0;
     let mut first_content = true;
     let mut prev_was_doc_close = false;
     let mut prev_was_declare = false;
@@ -516,14 +564,26 @@ pub fn reindent_php_block(code: &str, pad: &str) -> String {
     let mut deferred_lines: Vec<String> = Vec::new();
 
     let mut in_docblock = false;
+    let mut in_block_comment = false;
     let mut docblock_bodies: Vec<String> = Vec::new();
 
+    let reindent_heredocs = config.reindent_heredocs && config.targets_php(7, 3);
+    let mut heredoc_body: Vec<&str> = Vec::new();
+    let mut heredoc_indent = String::new();
+
     for line in code.lines() {
         if let Some(ref marker) = heredoc_marker {
-            result.push_str(line);
-            result.push('\n');
-            let closing = line.trim().trim_end_matches(';');
-            if closing == marker.as_str() {
+            let closing = is_heredoc_closing(line, marker);
+            if !reindent_heredocs {
+                result.push_str(line);
+                result.push('\n');
+            } else if closing {
+                push_heredoc(&heredoc_body, line, &heredoc_indent, &mut result);
+                heredoc_body.clear();
+            } else {
+                heredoc_body.push(line);
+            }
+            if closing {
                 let m = marker.clone();
                 heredoc_marker = None;
                 let after_marker = line.trim().strip_prefix(m.as_str()).unwrap_or("");
@@ -585,14 +645,16 @@ pub fn reindent_php_block(code: &str, pad: &str) -> String {
         }
 
         if trimmed.is_empty() {
-            if !prev_blank && !first_content {
+            if blank_run < config.max_blank_lines && !first_content {
                 if pending_docblocks.is_empty() && !in_docblock {
                     result.push('\n');
                 }
                 prev_blank = true;
             }
+            blank_run += 1;
             continue;
         }
+        blank_run = 0;
         if first_content && !prev_blank && is_header {
             result.push('\n');
         }
@@ -648,15 +710,27 @@ pub fn reindent_php_block(code: &str, pad: &str) -> String {
             continue;
         }
 
-        let formatted = format_php_code(trimmed);
+        let formatted = if in_block_comment {
+            trimmed.to_string()
+        } else {
+            format_php_code(trimmed, config)
+        };
+        in_block_comment = ends_inside_block_comment(trimmed, in_block_comment);
         emit_reindented_line(&formatted, pad, &mut depth, &mut result);
         if let Some(marker) = detect_heredoc(trimmed) {
             heredoc_marker = Some(marker);
+            let opener = result.trim_end_matches('\n').rsplit('\n').next().unwrap_or("");
+            heredoc_indent = opener[..opener.len() - opener.trim_start().len()].to_string();
         } else if has_unclosed_string(trimmed) {
             in_string = detect_open_quote(trimmed);
         }
     }
 
+    for line in heredoc_body {
+        result.push_str(line);
+        result.push('\n');
+    }
+
     if !pending_docblocks.is_empty() || !pending_descriptions.is_empty() {
         if !deferred_lines.is_empty() {
             for dl in &deferred_lines {
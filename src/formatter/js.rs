@@ -0,0 +1,388 @@
+use super::indent::INDENT;
+
+const REGEX_PRECEDING_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+const CONTINUATION_PREFIXES: &[&str] = &["?", ":", "&&", "||", "??"];
+
+enum Template {
+    Literal,
+    Expression(usize),
+}
+
+#[derive(Default)]
+struct JsScanner {
+    brackets: Vec<usize>,
+    templates: Vec<Template>,
+    in_block_comment: bool,
+    in_php: bool,
+    prev_char: Option<char>,
+    prev_word: String,
+    unbalanced: bool,
+}
+
+enum LineStart {
+    Verbatim,
+    Comment,
+    Code,
+}
+
+fn skip_quoted(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != quote {
+        if chars[i] == '\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    i + 1
+}
+
+fn skip_regex(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+impl JsScanner {
+    fn line_start(&self) -> LineStart {
+        if self.in_php || matches!(self.templates.last(), Some(Template::Literal)) {
+            LineStart::Verbatim
+        } else if self.in_block_comment {
+            LineStart::Comment
+        } else {
+            LineStart::Code
+        }
+    }
+
+    fn indent_level(&self) -> usize {
+        let mut level = 0;
+        let mut last_line = None;
+        for &line in &self.brackets {
+            if last_line != Some(line) {
+                level += 1;
+                last_line = Some(line);
+            }
+        }
+        level
+    }
+
+    fn pop_leading_closers(&mut self, trimmed: &str) -> usize {
+        let mut count = 0;
+        for ch in trimmed.chars() {
+            if !matches!(ch, '}' | ']' | ')') || self.brackets.pop().is_none() {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match self.prev_char {
+            None => true,
+            Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => {
+                REGEX_PRECEDING_KEYWORDS.contains(&self.prev_word.as_str())
+            }
+            Some(c) => !matches!(c, ')' | ']' | '}'),
+        }
+    }
+
+    fn note_significant(&mut self, ch: char) {
+        if ch.is_alphanumeric() || ch == '_' || ch == '$' {
+            if !self
+                .prev_char
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+            {
+                self.prev_word.clear();
+            }
+            self.prev_word.push(ch);
+        }
+        self.prev_char = Some(ch);
+    }
+
+    fn open_bracket(&mut self, ch: char, line: usize) {
+        if ch == '{'
+            && let Some(Template::Expression(depth)) = self.templates.last_mut()
+        {
+            *depth += 1;
+        }
+        self.brackets.push(line);
+    }
+
+    fn close_bracket(&mut self, ch: char) {
+        if ch == '}' {
+            match self.templates.last_mut() {
+                Some(Template::Expression(0)) => {
+                    self.templates.pop();
+                    return;
+                }
+                Some(Template::Expression(depth)) => *depth -= 1,
+                _ => {}
+            }
+        }
+        if self.brackets.pop().is_none() {
+            self.unbalanced = true;
+        }
+    }
+
+    fn scan_template(&mut self, chars: &[char], i: usize) -> usize {
+        match chars[i] {
+            '\\' => i + 2,
+            '`' => {
+                self.templates.pop();
+                self.prev_char = Some('`');
+                i + 1
+            }
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                self.templates.push(Template::Expression(0));
+                self.prev_char = Some('{');
+                i + 2
+            }
+            _ => i + 1,
+        }
+    }
+
+    fn scan_special(&mut self, chars: &[char], i: usize) -> Option<usize> {
+        let next = chars.get(i + 1).copied();
+        match (chars[i], next) {
+            ('<', Some('?')) => {
+                self.in_php = true;
+                Some(i + 2)
+            }
+            ('/', Some('/')) => Some(chars.len()),
+            ('/', Some('*')) => {
+                self.in_block_comment = true;
+                Some(i + 2)
+            }
+            ('/', _) if self.regex_allowed() => {
+                self.prev_char = Some('/');
+                Some(skip_regex(chars, i))
+            }
+            ('\'' | '"', _) => {
+                self.prev_char = Some(chars[i]);
+                Some(skip_quoted(chars, i))
+            }
+            ('`', _) => {
+                self.templates.push(Template::Literal);
+                Some(i + 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn scan_code(&mut self, chars: &[char], i: usize, line: usize) -> usize {
+        if let Some(next) = self.scan_special(chars, i) {
+            return next;
+        }
+        let ch = chars[i];
+        match ch {
+            '{' | '[' | '(' => self.open_bracket(ch, line),
+            '}' | ']' | ')' => self.close_bracket(ch),
+            _ => {}
+        }
+        if !ch.is_whitespace() {
+            self.note_significant(ch);
+        }
+        i + 1
+    }
+
+    fn scan_line(&mut self, chars: &[char], start: usize, line: usize) {
+        let mut i = start;
+        while i < chars.len() {
+            i = if self.in_php {
+                self.scan_until(chars, i, ['?', '>'])
+            } else if self.in_block_comment {
+                self.scan_until(chars, i, ['*', '/'])
+            } else if matches!(self.templates.last(), Some(Template::Literal)) {
+                self.scan_template(chars, i)
+            } else {
+                self.scan_code(chars, i, line)
+            };
+        }
+    }
+
+    fn scan_until(&mut self, chars: &[char], i: usize, end: [char; 2]) -> usize {
+        if chars[i] == end[0] && chars.get(i + 1) == Some(&end[1]) {
+            self.in_php = false;
+            self.in_block_comment = false;
+            return i + 2;
+        }
+        i + 1
+    }
+}
+
+fn is_continuation(trimmed: &str) -> bool {
+    (trimmed.starts_with('.') && !trimmed.starts_with("..."))
+        || CONTINUATION_PREFIXES
+            .iter()
+            .any(|p| trimmed.starts_with(p) && !trimmed.starts_with("?>"))
+}
+
+fn push_blank(out: &mut String, pending_blank: &mut bool) {
+    if *pending_blank && !out.is_empty() {
+        out.push('\n');
+    }
+    *pending_blank = false;
+}
+
+/// Reindents a script body line by line, nesting by unclosed brackets relative to `pad`.
+///
+/// Strings, template literals, regex literals, comments and `<?php ?>` segments are left
+/// untouched. Returns `None` when the brackets do not balance.
+pub fn reindent_js(source: &str, pad: &str) -> Option<String> {
+    let mut scanner = JsScanner::default();
+    let mut out = String::new();
+    let mut pending_blank = false;
+    for (line_no, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+        match scanner.line_start() {
+            LineStart::Verbatim => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            LineStart::Comment if !trimmed.is_empty() => {
+                push_blank(&mut out, &mut pending_blank);
+                let level = INDENT.repeat(scanner.indent_level());
+                let lead = if trimmed.starts_with('*') { " " } else { "" };
+                out.push_str(&format!("{pad}{level}{lead}{trimmed}\n"));
+            }
+            _ if trimmed.is_empty() => {
+                pending_blank = true;
+                continue;
+            }
+            _ => {
+                push_blank(&mut out, &mut pending_blank);
+                let skipped = scanner.pop_leading_closers(trimmed);
+                let extra = usize::from(is_continuation(trimmed));
+                let level = INDENT.repeat(scanner.indent_level() + extra);
+                out.push_str(&format!("{pad}{level}{trimmed}\n"));
+                let chars: Vec<char> = trimmed.chars().collect();
+                scanner.scan_line(&chars, skipped, line_no);
+                continue;
+            }
+        }
+        let chars: Vec<char> = line.chars().collect();
+        scanner.scan_line(&chars, 0, line_no);
+    }
+    let balanced = scanner.brackets.is_empty() && scanner.templates.is_empty() && !scanner.unbalanced;
+    if !balanced || scanner.in_block_comment || scanner.in_php {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn nested_blocks_reindented() {
+        let input = "\
+$(function() {
+        if (a) {
+    go();
+      }
+});";
+        let expected = "\
+$(function() {
+    if (a) {
+        go();
+    }
+});
+";
+        assert_eq!(reindent_js(input, "").unwrap(), expected);
+    }
+
+    #[test]
+    fn pad_applied_to_every_line() {
+        assert_eq!(reindent_js("a();\nb();", "    ").unwrap(), "    a();\n    b();\n");
+    }
+
+    #[test]
+    fn several_openers_on_one_line_indent_once() {
+        let input = "foo(function() {\nbar();\n});";
+        assert_eq!(reindent_js(input, "").unwrap(), "foo(function() {\n    bar();\n});\n");
+    }
+
+    #[test]
+    fn brackets_in_strings_and_comments_ignored() {
+        let input = "var s = '{';\n// }\n/* ( */\nx();";
+        assert_eq!(reindent_js(input, "").unwrap(), "var s = '{';\n// }\n/* ( */\nx();\n");
+    }
+
+    #[test]
+    fn template_literal_lines_kept_verbatim() {
+        let input = "var t = `\n  <div>{</div>\n`;\nif (x) {\ny();\n}";
+        let expected = "var t = `\n  <div>{</div>\n`;\nif (x) {\n    y();\n}\n";
+        assert_eq!(reindent_js(input, "").unwrap(), expected);
+    }
+
+    #[test]
+    fn regex_literal_with_brace() {
+        let input = "var r = /[{]+/g;\nif (r.test(s)) {\nok();\n}";
+        assert_eq!(
+            reindent_js(input, "").unwrap(),
+            "var r = /[{]+/g;\nif (r.test(s)) {\n    ok();\n}\n"
+        );
+    }
+
+    #[test]
+    fn php_echo_is_opaque() {
+        let input = "var data = <?= json_encode(['a' => [1]]) ?>;\nif (data) {\nrun(data);\n}";
+        let expected = "var data = <?= json_encode(['a' => [1]]) ?>;\nif (data) {\n    run(data);\n}\n";
+        assert_eq!(reindent_js(input, "").unwrap(), expected);
+    }
+
+    #[test]
+    fn chained_call_continuation_indented() {
+        let input = "$('#a')\n.on('click', go)\n.show();";
+        assert_eq!(
+            reindent_js(input, "").unwrap(),
+            "$('#a')\n    .on('click', go)\n    .show();\n"
+        );
+    }
+
+    #[test]
+    fn block_comment_lines_aligned() {
+        let input = "/**\n      * Docs\n      */\nrun();";
+        assert_eq!(reindent_js(input, "").unwrap(), "/**\n * Docs\n */\nrun();\n");
+    }
+
+    #[test]
+    fn blank_lines_collapsed() {
+        assert_eq!(reindent_js("\n\na();\n\n\n\nb();\n\n", "").unwrap(), "a();\n\nb();\n");
+    }
+
+    #[test]
+    fn unbalanced_rejected() {
+        assert_eq!(reindent_js("if (a) {", ""), None);
+        assert_eq!(reindent_js("}", ""), None);
+    }
+}
@@ -0,0 +1,316 @@
+use super::indent::INDENT;
+use crate::config::JsonStyle;
+
+#[derive(Debug, PartialEq)]
+enum JsonValue {
+    Scalar(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+/// Length of the run of ASCII digits at the start of `text`.
+fn digits(text: &str) -> usize {
+    text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+}
+
+/// Matches the JSON number grammar `-?(0|[1-9]\d*)(\.\d+)?([eE][+-]?\d+)?`.
+fn is_number(literal: &str) -> bool {
+    let rest = literal.strip_prefix('-').unwrap_or(literal);
+    let int = digits(rest);
+    if int == 0 || (int > 1 && rest.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &rest[int..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = digits(fraction);
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let len = digits(exponent);
+        if len == 0 {
+            return false;
+        }
+        rest = &exponent[len..];
+    }
+    rest.is_empty()
+}
+
+struct JsonParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, ch: char) -> Option<()> {
+        if self.peek()? != ch {
+            return None;
+        }
+        self.pos += 1;
+        Some(())
+    }
+
+    fn parse_value(&mut self) -> Option<JsonValue> {
+        match self.peek()? {
+            '{' => self.parse_object(),
+            '[' => self.parse_array(),
+            '"' => self.parse_string().map(JsonValue::Scalar),
+            _ => self.parse_literal(),
+        }
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut raw = String::from('"');
+        while let Some(&ch) = self.chars.get(self.pos) {
+            self.pos += 1;
+            raw.push(ch);
+            match ch {
+                '\\' => {
+                    raw.push(*self.chars.get(self.pos)?);
+                    self.pos += 1;
+                }
+                '"' => return Some(raw),
+                '\n' => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn parse_literal(&mut self) -> Option<JsonValue> {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            self.pos += 1;
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        let is_keyword = matches!(literal.as_str(), "true" | "false" | "null");
+        (is_keyword || is_number(&literal)).then_some(JsonValue::Scalar(literal))
+    }
+
+    fn parse_array(&mut self) -> Option<JsonValue> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        if self.peek()? == ']' {
+            self.pos += 1;
+            return Some(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            match self.peek()? {
+                ',' => self.pos += 1,
+                ']' => {
+                    self.pos += 1;
+                    return Some(JsonValue::Array(items));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Option<JsonValue> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        if self.peek()? == '}' {
+            self.pos += 1;
+            return Some(JsonValue::Object(entries));
+        }
+        loop {
+            self.peek()?;
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            entries.push((key, self.parse_value()?));
+            match self.peek()? {
+                ',' => self.pos += 1,
+                '}' => {
+                    self.pos += 1;
+                    return Some(JsonValue::Object(entries));
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn parse(source: &str) -> Option<JsonValue> {
+    if source.contains("<?") {
+        return None;
+    }
+    let chars: Vec<char> = source.chars().collect();
+    let mut parser = JsonParser { chars: &chars, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.peek().is_none().then_some(value)
+}
+
+fn write_compact(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::Scalar(s) => out.push_str(s),
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_compact(item, out);
+            }
+            out.push(']');
+        }
+        JsonValue::Object(entries) => {
+            out.push('{');
+            for (idx, (key, item)) in entries.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                out.push_str(key);
+                out.push(':');
+                write_compact(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_pretty(value: &JsonValue, pad: &str, out: &mut String) {
+    let inner = format!("{pad}{INDENT}");
+    match value {
+        JsonValue::Scalar(s) => out.push_str(s),
+        JsonValue::Array(items) if items.is_empty() => out.push_str("[]"),
+        JsonValue::Object(entries) if entries.is_empty() => out.push_str("{}"),
+        JsonValue::Array(items) => {
+            out.push_str("[\n");
+            for (idx, item) in items.iter().enumerate() {
+                out.push_str(&inner);
+                write_pretty(item, &inner, out);
+                out.push_str(if idx + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{pad}]"));
+        }
+        JsonValue::Object(entries) => {
+            out.push_str("{\n");
+            for (idx, (key, item)) in entries.iter().enumerate() {
+                out.push_str(&format!("{inner}{key}: "));
+                write_pretty(item, &inner, out);
+                out.push_str(if idx + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{pad}}}"));
+        }
+    }
+}
+
+/// Reprints a JSON document on lines prefixed with `pad`.
+///
+/// Returns `None` for invalid JSON or JSON containing PHP tags so the source stays untouched.
+pub fn format_json(source: &str, pad: &str, style: JsonStyle) -> Option<String> {
+    let value = parse(source)?;
+    let mut out = String::from(pad);
+    match style {
+        JsonStyle::Pretty => write_pretty(&value, pad, &mut out),
+        JsonStyle::Compact => write_compact(&value, &mut out),
+    }
+    out.push('\n');
+    Some(out)
+}
+
+/// Compacts a JSON attribute value onto a single line.
+pub fn compact_json(source: &str) -> Option<String> {
+    let value = parse(source)?;
+    let mut out = String::new();
+    write_compact(&value, &mut out);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pretty_nested_document() {
+        let input = r#"{"@type":"Product","offers":[{"price":9.5},{"price":1e3}],"tags":[],"meta":{}}"#;
+        let expected = r#"{
+    "@type": "Product",
+    "offers": [
+        {
+            "price": 9.5
+        },
+        {
+            "price": 1e3
+        }
+    ],
+    "tags": [],
+    "meta": {}
+}
+"#;
+        assert_eq!(format_json(input, "", JsonStyle::Pretty).unwrap(), expected);
+    }
+
+    #[test]
+    fn pretty_with_pad() {
+        assert_eq!(
+            format_json("[1, true, null]", "  ", JsonStyle::Pretty).unwrap(),
+            "  [\n      1,\n      true,\n      null\n  ]\n"
+        );
+    }
+
+    #[test]
+    fn compact_style() {
+        let input = "{\n  \"a\": [1, 2],\n  \"b\": \"x y\"\n}";
+        assert_eq!(
+            format_json(input, "", JsonStyle::Compact).unwrap(),
+            "{\"a\":[1,2],\"b\":\"x y\"}\n"
+        );
+    }
+
+    #[test]
+    fn escapes_preserved() {
+        assert_eq!(
+            compact_json(r#"{ "q": "say \"hi\"é" }"#).unwrap(),
+            r#"{"q":"say \"hi\"é"}"#
+        );
+    }
+
+    #[test]
+    fn php_inside_rejected() {
+        assert_eq!(compact_json(r#"{"a": <?= $x ?>}"#), None);
+    }
+
+    #[test]
+    fn invalid_json_rejected() {
+        assert_eq!(compact_json("{'a': 1}"), None);
+        assert_eq!(compact_json("{\"a\": 1,}"), None);
+        assert_eq!(compact_json("[1] [2]"), None);
+        assert_eq!(compact_json("{\"a\": undefined}"), None);
+        assert_eq!(compact_json("[NaN]"), None);
+    }
+
+    #[test]
+    fn number_grammar_enforced() {
+        assert_eq!(
+            compact_json("[0, -1, 1.5, -0.25e+3, 2E-8, 10]").unwrap(),
+            "[0,-1,1.5,-0.25e+3,2E-8,10]"
+        );
+        for bad in [
+            "-inf", "inf", "NaN", "-.5", ".5", "01", "-01", "1.", "1e", "1e+", "+1", "1.5.2", "0x1F",
+        ] {
+            assert_eq!(compact_json(&format!("[{bad}]")), None, "{bad}");
+        }
+    }
+}
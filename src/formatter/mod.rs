@@ -1,8 +1,16 @@
+pub mod alt_syntax;
+pub mod array;
+pub mod comment;
+pub mod css;
 pub mod docblock;
 pub mod echo;
 pub mod engine;
+pub mod heredoc;
 pub mod html;
 pub mod indent;
+pub mod js;
+pub mod json;
 pub mod php;
+pub mod prose;
 pub mod split;
 pub mod yii;
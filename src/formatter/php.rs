@@ -1,29 +1,194 @@
+use super::array::short_array_syntax;
+use crate::config::{ConcatSpacing, Config, QuoteStyle};
+
 const PHP_KEYWORDS: &[&str] = &[
     "if", "elseif", "else", "foreach", "for", "while", "switch", "catch", "match",
 ];
 
-pub fn format_php_code(code: &str) -> String {
+/// Operators matched longest first; spacing for each is decided by `format_operator`.
+const OPERATORS: &[&str] = &[
+    "<=>", "**=", "...", "??=", "<<=", ">>=", "===", "!==", "?->", "==", "!=", "<>", "<=", ">=", "&&", "||", "??",
+    "+=", "-=", "*=", "/=", ".=", "%=", "&=", "|=", "^=", "<<", ">>", "**", "=>", "->", "::", "++", "--", "+", "-",
+    "*", "/", "%", ".", "<", ">", "&", "|", "^", "=", "!", "~",
+];
+
+/// Operators printed exactly as written, without spaces around them.
+const TIGHT_OPERATORS: &[&str] = &["?->", "->", "::", "++", "--", "..."];
+
+/// Keywords after which `-`, `+` and `&` start an operand instead of a binary expression.
+const OPERAND_KEYWORDS: &[&str] = &[
+    "return", "echo", "print", "yield", "case", "throw", "and", "or", "xor", "as", "function", "fn", "else", "clone",
+];
+
+/// Modifiers that can precede a nullable property type such as `public ?int $id`.
+const MODIFIERS: &[&str] = &["public", "protected", "private", "static", "readonly", "var"];
+
+/// Reserved words, literals and built-in types written in lower case by PSR-12.
+const RESERVED_WORDS: &[&str] = &[
+    "abstract",
+    "and",
+    "array",
+    "as",
+    "bool",
+    "break",
+    "callable",
+    "case",
+    "catch",
+    "class",
+    "clone",
+    "const",
+    "continue",
+    "declare",
+    "default",
+    "die",
+    "do",
+    "echo",
+    "else",
+    "elseif",
+    "empty",
+    "enddeclare",
+    "endfor",
+    "endforeach",
+    "endif",
+    "endswitch",
+    "endwhile",
+    "eval",
+    "exit",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "fn",
+    "for",
+    "foreach",
+    "function",
+    "global",
+    "goto",
+    "if",
+    "implements",
+    "include",
+    "include_once",
+    "instanceof",
+    "insteadof",
+    "int",
+    "interface",
+    "isset",
+    "iterable",
+    "list",
+    "match",
+    "mixed",
+    "namespace",
+    "never",
+    "new",
+    "null",
+    "object",
+    "or",
+    "parent",
+    "print",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "require",
+    "require_once",
+    "return",
+    "self",
+    "static",
+    "string",
+    "switch",
+    "throw",
+    "trait",
+    "true",
+    "try",
+    "unset",
+    "use",
+    "var",
+    "void",
+    "while",
+    "xor",
+    "yield",
+];
+
+/// Reserved words added by later PHP versions, with the version that reserves them; below it
+/// they are ordinary names such as a `Match` class.
+const VERSIONED_WORDS: &[(&str, u32, u32)] = &[
+    ("fn", 7, 4),
+    ("match", 8, 0),
+    ("mixed", 8, 0),
+    ("never", 8, 1),
+    ("readonly", 8, 1),
+];
+
+/// Keywords followed by a class name.
+const CLASS_NAME_KEYWORDS: &[&str] = &["new", "instanceof", "extends", "implements"];
+
+/// Keywords that stay keywords before `::` and after `new`.
+const CLASS_KEYWORDS: &[&str] = &["self", "parent", "static"];
+
+const CASTS: &[&str] = &[
+    "int", "integer", "bool", "boolean", "float", "double", "real", "string", "array", "object", "unset", "binary",
+];
+
+pub fn format_php_code(code: &str, config: &Config) -> String {
+    if code.trim_start().starts_with('*') {
+        return code.to_string();
+    }
+    let converted;
+    let code = if config.short_array_syntax {
+        converted = short_array_syntax(code);
+        &converted
+    } else {
+        code
+    };
     let mut result = String::with_capacity(code.len());
     let chars: Vec<char> = code.chars().collect();
     let len = chars.len();
     let mut i = 0;
     let preserve_declare_equal = code.trim_start().starts_with("declare(");
+    let mut ternaries = 0;
 
     while i < len {
         let ch = chars[i];
 
         if ch == '\'' || ch == '"' {
-            i = skip_string_literal(&chars, i, &mut result);
+            i = format_string_literal(&chars, i, config, &mut result);
             continue;
         }
 
-        if ch == '=' && i + 1 < len && chars[i + 1] == '>' {
-            i = format_fat_arrow(&chars, i, &mut result);
+        if starts_comment(&chars, i) {
+            result.extend(&chars[i..]);
+            break;
+        }
+
+        if ch == '/' && chars.get(i + 1) == Some(&'*') {
+            i = copy_block_comment(&chars, i, &mut result);
+            continue;
+        }
+
+        if chars[i..].starts_with(&['<', '<', '<']) {
+            result.extend(&chars[i..]);
+            break;
+        }
+
+        if ch == '=' && preserve_declare_equal {
+            result.push(ch);
+            i += 1;
             continue;
         }
 
-        if ch == '=' && !preserve_declare_equal {
-            i = format_assignment_equal(&chars, i, &mut result);
+        if starts_number(&chars, i, &result) {
+            i = copy_number(&chars, i, &mut result);
+            continue;
+        }
+
+        if let Some(next) = format_ternary(&chars, i, &mut ternaries, &mut result) {
+            i = next;
+            continue;
+        }
+
+        if let Some(op) = OPERATORS.iter().find(|op| matches_at(&chars, i, op)) {
+            i = format_operator(&chars, i, op, (config, &mut result));
             continue;
         }
 
@@ -33,7 +198,7 @@ pub fn format_php_code(code: &str) -> String {
         }
 
         if ch.is_alphabetic() {
-            i = format_keyword(&chars, i, &mut result);
+            i = format_keyword(&chars, i, config, &mut result);
             continue;
         }
 
@@ -46,12 +211,23 @@ pub fn format_php_code(code: &str) -> String {
             continue;
         }
 
+        if ch == '(' {
+            if let Some(next) = format_cast(&chars, i, config, &mut result) {
+                i = next;
+                continue;
+            }
+        }
+
         if matches!(ch, '(' | '[') {
             result.push(ch);
             i += 1;
+            let spaced = i < len && chars[i] == ' ';
             while i < len && chars[i] == ' ' {
                 i += 1;
             }
+            if spaced && i < len && comment_end(&chars, i).is_some() {
+                result.push(' ');
+            }
             continue;
         }
 
@@ -62,12 +238,34 @@ pub fn format_php_code(code: &str) -> String {
     result
 }
 
-pub fn join_php_lines(code: &str) -> String {
-    code.lines()
+/// Joins the lines of a multi-line expression with single spaces, or `None` when a `//` or `#`
+/// comment is followed by more code, which joining would comment out.
+pub fn join_php_lines(code: &str) -> Option<String> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut scratch = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if matches!(chars[i], '\'' | '"') {
+            i = skip_string_literal(&chars, i, &mut scratch);
+            continue;
+        }
+        if let Some(end) = comment_end(&chars, i) {
+            if starts_comment(&chars, i) && chars[end..].iter().any(|c| !c.is_whitespace()) {
+                return None;
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    let joined = code
+        .lines()
         .map(|line| line.trim())
         .collect::<Vec<_>>()
         .join(" ")
         .replace(" ->", "->")
+        .replace(" ?->", "?->");
+    Some(joined)
 }
 
 fn has_method_call_after(chars: &[char], start: usize) -> bool {
@@ -118,15 +316,16 @@ pub fn split_by_chain(code: &str) -> Vec<String> {
             depth -= 1;
         }
 
-        if depth == 0 && chars[i] == '-' && i + 1 < len && chars[i + 1] == '>' {
+        let arrow = ["?->", "->"].into_iter().find(|op| matches_at(&chars, i, op));
+        if let Some(op) = arrow.filter(|_| depth == 0) {
             let prev = current.trim_end().chars().last().unwrap_or(' ');
-            let next_has_call = has_method_call_after(&chars, i + 2);
-            if prev == ')' || next_has_call {
+            if prev == ')' || has_method_call_after(&chars, i + op.len()) {
                 parts.push(current.trim_end().to_string());
-                current = String::from("->");
-                i += 2;
-                continue;
+                current.clear();
             }
+            current.push_str(op);
+            i += op.len();
+            continue;
         }
 
         current.push(chars[i]);
@@ -181,6 +380,12 @@ pub fn split_by_concat(code: &str) -> Vec<String> {
             depth -= 1;
         }
 
+        if matches_at(&chars, i, "...") {
+            current.push_str("...");
+            i += 3;
+            continue;
+        }
+
         if depth == 0 && ch == '.' {
             let prev = current.trim_end().chars().last();
             let mut j = i + 1;
@@ -423,69 +628,308 @@ fn skip_string_literal(chars: &[char], start: usize, result: &mut String) -> usi
     i
 }
 
-fn format_fat_arrow(chars: &[char], start: usize, result: &mut String) -> usize {
-    if !result.ends_with(' ') {
-        result.push(' ');
+/// Copies a string literal, printing a double-quoted one in single quotes when
+/// `php_quotes = "single"` and nothing in it is interpolated or escaped.
+fn format_string_literal(chars: &[char], start: usize, config: &Config, result: &mut String) -> usize {
+    if chars[start] == '"' && config.php_quotes == QuoteStyle::Single {
+        let end = (start + 1..chars.len()).find(|&j| matches!(chars[j], '"' | '\\' | '$' | '\''));
+        if let Some(close) = end.filter(|&j| chars[j] == '"') {
+            result.push('\'');
+            result.extend(&chars[start + 1..close]);
+            result.push('\'');
+            return close + 1;
+        }
     }
-    result.push_str("=>");
-    let i = start + 2;
-    if i < chars.len() && chars[i] != ' ' {
-        result.push(' ');
+    skip_string_literal(chars, start, result)
+}
+
+fn matches_at(chars: &[char], start: usize, text: &str) -> bool {
+    (start..)
+        .zip(text.chars())
+        .all(|(i, expected)| chars.get(i) == Some(&expected))
+}
+
+fn starts_comment(chars: &[char], i: usize) -> bool {
+    match chars[i] {
+        '/' => chars.get(i + 1) == Some(&'/'),
+        '#' => chars.get(i + 1) != Some(&'['),
+        _ => false,
     }
-    i
 }
 
-fn format_comma(chars: &[char], start: usize, result: &mut String) -> usize {
-    result.push(',');
-    let i = start + 1;
-    if i < chars.len() && chars[i] != ' ' && chars[i] != '\n' {
-        result.push(' ');
+/// Index just past the comment starting at `i`, if any; a line comment ends before its newline.
+pub fn comment_end(chars: &[char], i: usize) -> Option<usize> {
+    if starts_comment(chars, i) {
+        return Some((i..chars.len()).find(|&j| chars[j] == '\n').unwrap_or(chars.len()));
     }
-    i
+    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+        let end = (i + 2..chars.len()).find(|&j| chars[j] == '*' && chars.get(j + 1) == Some(&'/'));
+        return Some(end.map_or(chars.len(), |j| j + 2));
+    }
+    None
 }
 
-fn format_assignment_equal(chars: &[char], start: usize, result: &mut String) -> usize {
-    let len = chars.len();
-    let prev = if start > 0 { Some(chars[start - 1]) } else { None };
-    let next = if start + 1 < len { Some(chars[start + 1]) } else { None };
+/// Splits PHP code into its statements and a comment that ends it, both trimmed.
+pub fn split_trailing_comment(code: &str) -> (&str, &str) {
+    let chars: Vec<char> = code.chars().collect();
+    let mut scratch = String::new();
+    let mut tail = chars.len();
+    let mut i = 0;
+    while i < chars.len() {
+        if matches!(chars[i], '\'' | '"') {
+            i = skip_string_literal(&chars, i, &mut scratch);
+            continue;
+        }
+        if let Some(end) = comment_end(&chars, i) {
+            if chars[end..].iter().all(|c| c.is_whitespace()) {
+                tail = i;
+                break;
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    let split = code.char_indices().nth(tail).map_or(code.len(), |(pos, _)| pos);
+    let (statements, comment) = code.split_at(split);
+    (statements.trim(), comment.trim())
+}
+
+fn copy_block_comment(chars: &[char], start: usize, result: &mut String) -> usize {
+    let mut i = start + 2;
+    while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+        i += 1;
+    }
+    let end = (i + 2).min(chars.len());
+    result.extend(&chars[start..end]);
+    end
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
 
-    let is_non_assignment = next.is_some_and(|c| c == '=' || c == '>')
-        || prev.is_some_and(|c| {
-            matches!(
-                c,
-                '=' | '!' | '<' | '>' | '+' | '-' | '*' | '/' | '%' | '.' | '&' | '|' | '^' | '?'
-            )
-        });
+fn starts_number(chars: &[char], i: usize, result: &str) -> bool {
+    let leading_dot = chars[i] == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit) && !ends_operand(result);
+    let digit = chars[i].is_ascii_digit() && !result.ends_with(|c: char| is_word_char(c) || c == '$');
+    leading_dot || digit
+}
+
+/// Copies a numeric literal, including decimal points, `_` separators and exponent signs.
+fn copy_number(chars: &[char], start: usize, result: &mut String) -> usize {
+    let hex = chars[start] == '0' && matches!(chars.get(start + 1), Some('x' | 'X'));
+    let mut i = start;
+    while i < chars.len() {
+        let c = chars[i];
+        let decimal = c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+        let exponent_sign = matches!(c, '+' | '-') && !hex && matches!(chars[i - 1], 'e' | 'E');
+        if !(is_word_char(c) || decimal || exponent_sign) {
+            break;
+        }
+        i += 1;
+    }
+    result.extend(&chars[start..i]);
+    i
+}
 
-    if is_non_assignment {
-        result.push('=');
-        return start + 1;
+/// Reports whether the output so far ends with a complete operand, so a following
+/// `-`, `+` or `&` is a binary operator.
+fn ends_operand(result: &str) -> bool {
+    let trimmed = result.trim_end();
+    let Some(last) = trimmed.chars().last() else {
+        return false;
+    };
+    if CASTS.iter().any(|cast| trimmed.ends_with(&format!("({cast})"))) {
+        return false;
+    }
+    if trimmed.ends_with("++") || trimmed.ends_with("--") {
+        return true;
     }
+    if is_word_char(last) {
+        let word_start = trimmed.trim_end_matches(is_word_char);
+        let word = &trimmed[word_start.len()..];
+        let variable = word_start.ends_with('$');
+        return variable || !OPERAND_KEYWORDS.contains(&word.to_lowercase().as_str());
+    }
+    matches!(last, ')' | ']' | '}' | '\'' | '"')
+}
 
-    if !result.ends_with(' ') && !result.ends_with('\n') && !result.ends_with('\t') {
+fn push_space_before(result: &mut String) {
+    let trimmed = result.trim_end_matches(' ');
+    if trimmed.is_empty() || trimmed.ends_with('\n') {
+        return;
+    }
+    result.truncate(trimmed.len());
+    if !result.ends_with(['\t', '\n']) {
         result.push(' ');
     }
-    result.push('=');
+}
 
-    let mut i = start + 1;
-    while i < len && chars[i] == ' ' {
+fn skip_spaces(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && matches!(chars[i], ' ' | '\t') {
         i += 1;
     }
-    if i < len && chars[i] != ' ' && chars[i] != '\n' && chars[i] != '\t' && chars[i] != '\r' {
+    i
+}
+
+fn push_space_after(chars: &[char], i: usize, result: &mut String) -> usize {
+    let next = skip_spaces(chars, i);
+    if next < chars.len() && !matches!(chars[next], '\n' | '\r') {
         result.push(' ');
     }
+    next
+}
+
+/// `A|B` and `A&B` between names are type unions/intersections or constant flags; keep them as written.
+fn is_type_separator(chars: &[char], i: usize, op: &str) -> bool {
+    matches!(op, "|" | "&")
+        && i > 0
+        && (is_word_char(chars[i - 1]) || chars[i - 1] == '\\')
+        && chars
+            .get(i + 1)
+            .is_some_and(|&c| c.is_alphabetic() || matches!(c, '\\' | '?' | '_'))
+}
+
+fn format_operator(chars: &[char], start: usize, op: &str, ctx: (&Config, &mut String)) -> usize {
+    let (config, result) = ctx;
+    let end = start + op.chars().count();
+    if TIGHT_OPERATORS.contains(&op) || is_type_separator(chars, start, op) {
+        result.push_str(op);
+        return end;
+    }
+    let unary = matches!(op, "!" | "~") || (matches!(op, "-" | "+" | "&") && !ends_operand(result));
+    if unary {
+        result.push_str(op);
+        return skip_spaces(chars, end);
+    }
+    if op == "&" && matches!(chars.get(end), Some('$')) && start > 0 && chars[start - 1] == ' ' {
+        result.push_str(op);
+        return end;
+    }
+    if op == "." && config.concat_spacing == ConcatSpacing::Tight {
+        result.truncate(result.trim_end_matches(' ').len());
+        result.push('.');
+        return skip_spaces(chars, end);
+    }
+    push_space_before(result);
+    result.push_str(op);
+    push_space_after(chars, end, result)
+}
 
+/// Spaces the `?`, `:` and `?:` of a ternary. A `:` counts only after an unmatched ternary `?`,
+/// so `::`, alternative-syntax, `case` and return-type colons are left alone.
+fn format_ternary(chars: &[char], start: usize, open: &mut usize, result: &mut String) -> Option<usize> {
+    match chars[start] {
+        '?' if ends_operand(result) && !matches_at(chars, start, "?->") && !matches_at(chars, start, "??") => {
+            let before = result.trim_end();
+            let word = &before[before.trim_end_matches(is_word_char).len()..];
+            if MODIFIERS.contains(&word.to_lowercase().as_str()) {
+                return None;
+            }
+            push_space_before(result);
+            let after = skip_spaces(chars, start + 1);
+            if chars.get(after) == Some(&':') && chars.get(after + 1) != Some(&':') {
+                result.push_str("?:");
+                return Some(push_space_after(chars, after + 1, result));
+            }
+            *open += 1;
+            result.push('?');
+            Some(push_space_after(chars, start + 1, result))
+        }
+        ':' if *open > 0 && chars.get(start + 1) != Some(&':') => {
+            *open -= 1;
+            push_space_before(result);
+            result.push(':');
+            Some(push_space_after(chars, start + 1, result))
+        }
+        _ => None,
+    }
+}
+
+/// Prints a cast such as `(int)` with one space before its operand.
+fn format_cast(chars: &[char], start: usize, config: &Config, result: &mut String) -> Option<usize> {
+    let close = start + chars[start..].iter().position(|&c| c == ')')?;
+    let inner: String = chars[start + 1..close].iter().collect();
+    let cast = inner.trim().to_lowercase();
+    if !CASTS.contains(&cast.as_str()) {
+        return None;
+    }
+    result.push('(');
+    result.push_str(if config.lowercase_php_keywords {
+        &cast
+    } else {
+        inner.trim()
+    });
+    result.push(')');
+    Some(push_space_after(chars, close + 1, result))
+}
+
+fn format_comma(chars: &[char], start: usize, result: &mut String) -> usize {
+    result.push(',');
+    let i = start + 1;
+    if i < chars.len() && chars[i] != ' ' && chars[i] != '\n' {
+        result.push(' ');
+    }
     i
 }
 
-fn format_keyword(chars: &[char], start: usize, result: &mut String) -> usize {
+/// Reports whether the word ending at `end`, written after `result`, names an argument as in `f(text: $t)`.
+fn is_named_argument(chars: &[char], end: usize, result: &str) -> bool {
+    let colon = skip_spaces(chars, end);
+    chars.get(colon) == Some(&':') && chars.get(colon + 1) != Some(&':') && result.trim_end().ends_with(['(', ','])
+}
+
+/// Reports whether the word ending at `end` names a variable, member, namespace part or
+/// declared function/constant rather than a keyword.
+fn is_identifier_position(chars: &[char], end: usize, result: &str) -> bool {
+    let before = result.trim_end();
+    let previous = before[before.trim_end_matches(is_word_char).len()..].to_lowercase();
+    let declared = ["function", "const"].contains(&previous.as_str());
+    let class_name =
+        CLASS_NAME_KEYWORDS.contains(&previous.as_str()) || matches_at(chars, skip_spaces(chars, end), "::");
+    let start = chars[..end]
+        .iter()
+        .rposition(|&c| !is_word_char(c))
+        .map_or(0, |pos| pos + 1);
+    let word: String = chars[start..end].iter().collect();
+    let class_keyword = CLASS_KEYWORDS.contains(&word.to_lowercase().as_str());
+    result.ends_with(['$', '\\'])
+        || is_named_argument(chars, end, result)
+        || before.ends_with("->")
+        || before.ends_with("::")
+        || chars.get(end) == Some(&'\\')
+        || declared
+        || (class_name && !class_keyword)
+}
+
+/// Reports whether `word`, in lower case, is reserved by the configured PHP version.
+fn is_reserved(word: &str, config: &Config) -> bool {
+    let added = VERSIONED_WORDS.iter().find(|(name, _, _)| *name == word);
+    RESERVED_WORDS.contains(&word) && added.is_none_or(|&(_, major, minor)| config.targets_php(major, minor))
+}
+
+fn format_keyword(chars: &[char], start: usize, config: &Config, result: &mut String) -> usize {
     let len = chars.len();
     let mut i = start;
 
     while i < len && (chars[i].is_alphanumeric() || chars[i] == '_') {
         i += 1;
     }
-    let word: String = chars[start..i].iter().collect();
+    let mut word: String = chars[start..i].iter().collect();
+    let lower = word.to_lowercase();
+    if config.lowercase_php_keywords
+        && lower != word
+        && is_reserved(&lower, config)
+        && !is_identifier_position(chars, i, result)
+    {
+        word = lower;
+    }
+
+    if is_named_argument(chars, i, result) {
+        result.push_str(&word);
+        result.push(':');
+        return push_space_after(chars, skip_spaces(chars, i) + 1, result);
+    }
 
     if PHP_KEYWORDS.contains(&word.as_str()) && i < len && chars[i] == '(' {
         result.push_str(&word);
@@ -502,75 +946,70 @@ mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    fn format(code: &str) -> String {
+        format_php_code(code, &Config::default())
+    }
+
     #[test]
     fn keyword_space() {
-        assert_eq!(format_php_code("if($x):"), "if ($x):");
+        assert_eq!(format("if($x):"), "if ($x):");
     }
 
     #[test]
     fn keyword_already_spaced() {
-        assert_eq!(format_php_code("if ($x):"), "if ($x):");
+        assert_eq!(format("if ($x):"), "if ($x):");
     }
 
     #[test]
     fn foreach_keyword() {
-        assert_eq!(
-            format_php_code("foreach($items as $item):"),
-            "foreach ($items as $item):"
-        );
+        assert_eq!(format("foreach($items as $item):"), "foreach ($items as $item):");
     }
 
     #[test]
     fn arrow_spacing() {
-        assert_eq!(format_php_code("'id'=>$item->id"), "'id' => $item->id");
+        assert_eq!(format("'id'=>$item->id"), "'id' => $item->id");
     }
 
     #[test]
     fn arrow_already_spaced() {
-        assert_eq!(format_php_code("'id' => $item->id"), "'id' => $item->id");
+        assert_eq!(format("'id' => $item->id"), "'id' => $item->id");
     }
 
     #[test]
     fn comma_spacing() {
-        assert_eq!(format_php_code("$a,$b,$c"), "$a, $b, $c");
+        assert_eq!(format("$a,$b,$c"), "$a, $b, $c");
     }
 
     #[test]
     fn comma_already_spaced() {
-        assert_eq!(format_php_code("$a, $b, $c"), "$a, $b, $c");
+        assert_eq!(format("$a, $b, $c"), "$a, $b, $c");
     }
 
     #[test]
     fn object_arrow_untouched() {
-        assert_eq!(format_php_code("$model->title"), "$model->title");
+        assert_eq!(format("$model->title"), "$model->title");
     }
 
     #[test]
     fn string_content_untouched() {
-        assert_eq!(
-            format_php_code("Html::a('foo=>bar','baz')"),
-            "Html::a('foo=>bar', 'baz')"
-        );
+        assert_eq!(format("Html::a('foo=>bar','baz')"), "Html::a('foo=>bar', 'baz')");
     }
 
     #[test]
     fn complex_yii_call() {
         let input = "Html::a($item->name,['item/view','id'=>$item->id],['class'=>'btn btn-primary'])";
         let expected = "Html::a($item->name, ['item/view', 'id' => $item->id], ['class' => 'btn btn-primary'])";
-        assert_eq!(format_php_code(input), expected);
+        assert_eq!(format(input), expected);
     }
 
     #[test]
     fn endif_unchanged() {
-        assert_eq!(format_php_code("endif;"), "endif;");
+        assert_eq!(format("endif;"), "endif;");
     }
 
     #[test]
     fn echo_expression() {
-        assert_eq!(
-            format_php_code("Html::encode($model->title)"),
-            "Html::encode($model->title)"
-        );
+        assert_eq!(format("Html::encode($model->title)"), "Html::encode($model->title)");
     }
 
     #[test]
@@ -584,20 +1023,169 @@ mod tests {
 
     #[test]
     fn assignment_spacing() {
-        assert_eq!(format_php_code("$name='World';"), "$name = 'World';");
+        assert_eq!(format("$name='World';"), "$name = 'World';");
+    }
+
+    #[test]
+    fn comparison_and_assignment_operators_spaced() {
+        assert_eq!(format("$a==$b"), "$a == $b");
+        assert_eq!(format("$a!=$b"), "$a != $b");
+        assert_eq!(format("$a>=$b"), "$a >= $b");
+        assert_eq!(format("$a<=$b"), "$a <= $b");
+        assert_eq!(format("$a??=$b"), "$a ??= $b");
     }
 
     #[test]
-    fn assignment_keeps_comparisons() {
-        assert_eq!(format_php_code("$a==$b"), "$a==$b");
-        assert_eq!(format_php_code("$a!=$b"), "$a!=$b");
-        assert_eq!(format_php_code("$a>=$b"), "$a>=$b");
-        assert_eq!(format_php_code("$a<=$b"), "$a<=$b");
-        assert_eq!(format_php_code("$a??=$b"), "$a??=$b");
+    fn ternary_operators_spaced() {
+        assert_eq!(format("$b?:$c"), "$b ?: $c");
+        assert_eq!(format("$a? $b :$c"), "$a ? $b : $c");
+        assert_eq!(format("$a?1:($b?2:3)"), "$a ? 1 : ($b ? 2 : 3)");
+        assert_eq!(format("$a ? f(x: 1) : A::B"), "$a ? f(x: 1) : A::B");
+        assert_eq!(format("$a?->b ?? $c"), "$a?->b ?? $c");
+        assert_eq!(format("if ($a):"), "if ($a):");
+        assert_eq!(format("case 1:"), "case 1:");
+        assert_eq!(format("function (?int $a): ?string"), "function (?int $a): ?string");
+        assert_eq!(format("public ?int $id;"), "public ?int $id;");
     }
 
     #[test]
     fn declare_equal_unchanged() {
-        assert_eq!(format_php_code("declare(strict_types=1);"), "declare(strict_types=1);");
+        assert_eq!(format("declare(strict_types=1);"), "declare(strict_types=1);");
+    }
+
+    #[test]
+    fn binary_operators_spaced() {
+        assert_eq!(format("$a+$b*$c"), "$a + $b * $c");
+        assert_eq!(format("$x===$y&&$z<=>$w"), "$x === $y && $z <=> $w");
+        assert_eq!(format("$a??$b  ||  $c"), "$a ?? $b || $c");
+        assert_eq!(format("$s.'x'.PHP_EOL"), "$s . 'x' . PHP_EOL");
+        assert_eq!(format("$n.=$i%2"), "$n .= $i % 2");
+    }
+
+    #[test]
+    fn unary_operators_and_casts() {
+        assert_eq!(format("$a=-1"), "$a = -1");
+        assert_eq!(format("f(-$x, [- 2], $y-1)"), "f(-$x, [-2], $y - 1)");
+        assert_eq!(format("return -1.5e-3;"), "return -1.5e-3;");
+        assert_eq!(format("! $x&&!$y"), "!$x && !$y");
+        assert_eq!(format("(int)$v+(string) $w"), "(int) $v + (string) $w");
+        assert_eq!(format("$i++ + --$j"), "$i++ + --$j");
+    }
+
+    #[test]
+    fn member_access_references_and_variadics_untouched() {
+        assert_eq!(format("$a?->b()::C"), "$a?->b()::C");
+        assert_eq!(format("foreach ($rows as $k=>&$row)"), "foreach ($rows as $k => &$row)");
+        assert_eq!(
+            format("function f(array &$a, int|string ...$rest)"),
+            "function f(array &$a, int|string ...$rest)"
+        );
+        assert_eq!(format("$x = 1; // a+b"), "$x = 1; // a+b");
+        assert_eq!(format("$this->registerJs(<<<JS"), "$this->registerJs(<<<JS");
+    }
+
+    #[test]
+    fn concat_spacing_tight() {
+        let config = Config {
+            concat_spacing: ConcatSpacing::Tight,
+            ..Config::default()
+        };
+        assert_eq!(format_php_code("$a . 'x'.$b .= 1.5", &config), "$a.'x'.$b .= 1.5");
+    }
+
+    #[test]
+    fn keywords_and_constants_lowercased() {
+        assert_eq!(format("IF($x===NULL):"), "if ($x === null):");
+        assert_eq!(format("ENDFOREACH;"), "endforeach;");
+        assert_eq!(
+            format("return Array(TRUE, (BOOL)$v);"),
+            "return array(true, (bool) $v);"
+        );
+        assert_eq!(
+            format("$TRUE->Null(Foo::NULL, 'NULL', \\App\\Null\\Print)"),
+            "$TRUE->Null(Foo::NULL, 'NULL', \\App\\Null\\Print)"
+        );
+        assert_eq!(
+            format("public function List(): Static"),
+            "public function List(): static"
+        );
+    }
+
+    #[test]
+    fn class_names_keep_their_case() {
+        assert_eq!(format("new Match($a)"), "new Match($a)");
+        assert_eq!(format("Mixed::of(), Fn($x)"), "Mixed::of(), Fn($x)");
+        assert_eq!(
+            format("$a Instanceof Never || $b INSTANCEOF Readonly"),
+            "$a instanceof Never || $b instanceof Readonly"
+        );
+        assert_eq!(
+            format("class A Extends List Implements Match"),
+            "class A extends List implements Match"
+        );
+        assert_eq!(
+            format("New Static(Self::X, PARENT::y())"),
+            "new static(self::X, parent::y())"
+        );
+        let php81 = Config {
+            php_version: "8.1".into(),
+            ..Config::default()
+        };
+        assert_eq!(
+            format_php_code("MATCH($a) { 1 => Fn() => NEVER } + Mixed::of()", &php81),
+            "match ($a) { 1 => fn() => never } + Mixed::of()"
+        );
+        assert_eq!(format("Match($a)"), "Match($a)");
+    }
+
+    #[test]
+    fn join_refuses_to_comment_out_code() {
+        assert_eq!(
+            join_php_lines("$a\n    ->b() /* x */\n    . $c // end").as_deref(),
+            Some("$a->b() /* x */ . $c // end")
+        );
+        assert_eq!(join_php_lines("$a // first\n    . $b"), None);
+        assert_eq!(
+            join_php_lines("'# not'\n    . '// comments'").as_deref(),
+            Some("'# not' . '// comments'")
+        );
+    }
+
+    #[test]
+    fn php8_syntax_preserved() {
+        assert_eq!(format("Html::a(text:$t, url :$u)"), "Html::a(text: $t, url: $u)");
+        assert_eq!(format("f(NULL: 1, $a ? B : C)"), "f(NULL: 1, $a ? B : C)");
+        assert_eq!(
+            format("#[Pure] static fn(int ...$n) => Status::Active?->label()"),
+            "#[Pure] static fn(int ...$n) => Status::Active?->label()"
+        );
+        assert_eq!(
+            format("array_map(strlen(...), [...$a, ...$b])"),
+            "array_map(strlen(...), [...$a, ...$b])"
+        );
+    }
+
+    #[test]
+    fn nullsafe_calls_split_as_chain() {
+        assert_eq!(split_by_chain("$a?->b()?->c->d()"), ["$a", "?->b()", "?->c", "->d()"]);
+        assert_eq!(split_by_concat("f(...$a) . $b"), ["f(...$a)", "$b"]);
+    }
+
+    #[test]
+    fn plain_double_quoted_strings_single_quoted() {
+        let config = Config {
+            php_quotes: QuoteStyle::Single,
+            ..Config::default()
+        };
+        let code = r#"Html::a("Home", ["site/index"], ["title" => "It's", "data-id" => "$id", "x" => "{$a}\n"])"#;
+        assert_eq!(
+            format_php_code(code, &config),
+            r#"Html::a('Home', ['site/index'], ['title' => "It's", 'data-id' => "$id", 'x' => "{$a}\n"])"#
+        );
+        assert_eq!(
+            format_php_code(r#"'say "hi"' . "" // "x""#, &config),
+            r#"'say "hi"' . '' // "x""#
+        );
+        assert_eq!(format(r#"["a" => "b"]"#), r#"["a" => "b"]"#);
     }
 }
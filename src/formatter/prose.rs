@@ -0,0 +1,112 @@
+use super::indent::MAX_LINE_LENGTH;
+use crate::config::ProseWrap;
+
+/// A piece of flowing content inside a block element.
+#[derive(Debug, PartialEq)]
+pub enum Piece<'a> {
+    /// Source text that may break at any whitespace.
+    Text(&'a str),
+    /// Formatted markup, e.g. `<a href="#">x</a>` or `<?= $x ?>`, that is never broken.
+    Atom(String),
+}
+
+/// Splits pieces into words, gluing atoms to text they touch without whitespace.
+fn words(pieces: &[Piece]) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut glue = false;
+    for piece in pieces {
+        match piece {
+            Piece::Atom(atom) => {
+                match words.last_mut() {
+                    Some(last) if glue => last.push_str(atom),
+                    _ => words.push(atom.clone()),
+                }
+                glue = true;
+            }
+            Piece::Text(text) if text.trim().is_empty() => glue &= text.is_empty(),
+            Piece::Text(text) => {
+                let attach = glue && !text.starts_with(char::is_whitespace);
+                for (idx, word) in text.split_whitespace().enumerate() {
+                    match words.last_mut() {
+                        Some(last) if idx == 0 && attach => last.push_str(word),
+                        _ => words.push(word.to_string()),
+                    }
+                }
+                glue = !text.ends_with(char::is_whitespace);
+            }
+        }
+    }
+    words
+}
+
+/// Lays out a run of text and atoms on lines prefixed with `pad`.
+///
+/// `never` joins the run onto one line, `always` fills lines up to `MAX_LINE_LENGTH`;
+/// a word longer than the limit gets a line of its own.
+pub fn reflow(pieces: &[Piece], pad: &str, mode: ProseWrap) -> String {
+    let mut out = String::new();
+    let mut line = String::new();
+    for word in words(pieces) {
+        let overflows = pad.len() + line.len() + 1 + word.len() > MAX_LINE_LENGTH;
+        if !line.is_empty() && mode == ProseWrap::Always && overflows {
+            out.push_str(&format!("{pad}{line}\n"));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        out.push_str(&format!("{pad}{line}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn always_fills_lines_to_limit() {
+        let text = "lorem ipsum ".repeat(15);
+        let out = reflow(&[Piece::Text(&text)], "    ", ProseWrap::Always);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.len() <= MAX_LINE_LENGTH && l.starts_with("    ") && !l.starts_with("     "))
+        );
+    }
+
+    #[test]
+    fn never_joins_lines() {
+        let out = reflow(&[Piece::Text("first\n    second\n third")], "", ProseWrap::Never);
+        assert_eq!(out, "first second third\n");
+    }
+
+    #[test]
+    fn atoms_glue_to_touching_text() {
+        let pieces = [
+            Piece::Text("See the "),
+            Piece::Atom("<a href=\"#\">guide</a>".into()),
+            Piece::Text(", then "),
+            Piece::Atom("<?= $name ?>".into()),
+            Piece::Text("\n"),
+            Piece::Atom("<b>now</b>".into()),
+        ];
+        assert_eq!(
+            words(&pieces),
+            vec![
+                "See",
+                "the",
+                "<a href=\"#\">guide</a>,",
+                "then",
+                "<?= $name ?>",
+                "<b>now</b>"
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::array::array_literal;
 use super::indent::{INDENT, MAX_LINE_LENGTH};
 use super::php::{split_by_args, split_by_commas};
 
@@ -62,6 +63,10 @@ pub fn find_ternary_positions(code: &str) -> Option<(usize, usize)> {
                     i += 2;
                     continue;
                 }
+                if code[i..].starts_with("?->") {
+                    i += 3;
+                    continue;
+                }
                 question_pos = Some(i);
             }
             b':' if depth == 0 && question_pos.is_some() => {
@@ -130,13 +135,13 @@ pub fn try_split_long_line(formatted: &str, base_pad: &str) -> Option<String> {
     let inner: String = chars[open_pos + 1..close_pos].iter().collect();
     let inner = inner.trim();
 
-    if let Some(array_inner) = inner.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+    if let Some((open, array_inner, close)) = array_literal(inner) {
         let items = split_by_commas(array_inner);
         if items.len() > 1 {
             let prefix: String = chars[..=open_pos].iter().collect();
             let suffix: String = chars[close_pos..].iter().collect();
-            let new_prefix = format!("{prefix}[");
-            let new_suffix = format!("]{suffix}");
+            let new_prefix = format!("{prefix}{open}");
+            let new_suffix = format!("{close}{suffix}");
             return Some(build_split(&new_prefix, &items, &new_suffix, base_pad));
         }
     }
@@ -184,11 +189,7 @@ pub fn build_split(prefix: &str, args: &[String], suffix: &str, pad: &str) -> St
 }
 
 pub fn expand_bare_array(arg: &str, pad: &str) -> Option<String> {
-    let trimmed = arg.trim();
-    if !trimmed.starts_with('[') || !trimmed.ends_with(']') {
-        return None;
-    }
-    let inner = &trimmed[1..trimmed.len() - 1];
+    let (open, inner, close) = array_literal(arg.trim())?;
     let items = split_by_commas(inner);
     if items.len() <= 1 {
         if items.len() == 1 {
@@ -196,7 +197,7 @@ pub fn expand_bare_array(arg: &str, pad: &str) -> Option<String> {
             let nested_pad = format!("{pad}{INDENT}");
             let item_line_len = nested_pad.len() + item.len() + 1;
             if item_line_len > MAX_LINE_LENGTH {
-                let mut result = format!("{pad}[\n");
+                let mut result = format!("{pad}{open}\n");
                 if let Some(split) = try_split_long_line(item, &nested_pad) {
                     let trimmed = split.trim_end_matches('\n');
                     result.push_str(trimmed);
@@ -204,14 +205,14 @@ pub fn expand_bare_array(arg: &str, pad: &str) -> Option<String> {
                 } else {
                     result.push_str(&format!("{nested_pad}{item},\n"));
                 }
-                result.push_str(&format!("{pad}],\n"));
+                result.push_str(&format!("{pad}{close},\n"));
                 return Some(result);
             }
         }
         return None;
     }
     let nested_pad = format!("{pad}{INDENT}");
-    let mut result = format!("{pad}[\n");
+    let mut result = format!("{pad}{open}\n");
     for item in &items {
         let item_line_len = nested_pad.len() + item.len() + 1;
         if item_line_len > MAX_LINE_LENGTH {
@@ -220,36 +221,32 @@ pub fn expand_bare_array(arg: &str, pad: &str) -> Option<String> {
                 continue;
             }
         }
-        if item.starts_with('[') && item.ends_with(']') {
-            let sub_inner = &item[1..item.len() - 1];
+        if let Some((sub_open, sub_inner, sub_close)) = array_literal(item) {
             let sub_items = split_by_commas(sub_inner);
             if sub_items.len() > 1 {
                 let deeper_pad = format!("{nested_pad}{INDENT}");
-                result.push_str(&format!("{nested_pad}[\n"));
+                result.push_str(&format!("{nested_pad}{sub_open}\n"));
                 for sub in &sub_items {
                     result.push_str(&format!("{deeper_pad}{sub},\n"));
                 }
-                result.push_str(&format!("{nested_pad}],\n"));
+                result.push_str(&format!("{nested_pad}{sub_close},\n"));
                 continue;
             }
         }
         result.push_str(&format!("{nested_pad}{item},\n"));
     }
-    result.push_str(&format!("{pad}],\n"));
+    result.push_str(&format!("{pad}{close},\n"));
     Some(result)
 }
 
 pub fn expand_bare_sub_array(item: &str, pad: &str) -> Option<String> {
-    if !item.starts_with('[') || !item.ends_with(']') {
-        return None;
-    }
-    let sub_inner = &item[1..item.len() - 1];
+    let (open, sub_inner, close) = array_literal(item)?;
     let sub_items = split_by_commas(sub_inner);
     if sub_items.len() <= 1 {
         return None;
     }
     let deeper_pad = format!("{pad}{INDENT}");
-    let mut result = format!("{pad}[\n");
+    let mut result = format!("{pad}{open}\n");
     for sub in &sub_items {
         let sub_line_len = deeper_pad.len() + sub.len() + 1;
         if sub_line_len > MAX_LINE_LENGTH {
@@ -270,7 +267,7 @@ pub fn expand_bare_sub_array(item: &str, pad: &str) -> Option<String> {
         }
         result.push_str(&format!("{deeper_pad}{sub},\n"));
     }
-    result.push_str(&format!("{pad}],\n"));
+    result.push_str(&format!("{pad}{close},\n"));
     Some(result)
 }
 
@@ -501,20 +498,29 @@ pub fn find_array_arrow(arg: &str) -> Option<(usize, usize)> {
     Some((i, arrow_pos))
 }
 
+/// Splits a named argument `name: value` into `name:` and the value.
+fn named_argument(arg: &str) -> Option<(&str, &str)> {
+    let end = arg.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    let rest = &arg[end..];
+    let named = end > 0 && !arg.starts_with(|c: char| c.is_ascii_digit()) && rest.starts_with(':');
+    (named && !rest.starts_with("::")).then(|| (&arg[..=end], rest[1..].trim()))
+}
+
 pub fn expand_nested_array(arg: &str, pad: &str) -> Option<String> {
-    let (skip, arrow_pos) = find_array_arrow(arg)?;
-    let value = arg[skip + arrow_pos + 2..].trim();
-    if !value.starts_with('[') || !value.ends_with(']') {
-        return None;
-    }
-    let inner = &value[1..value.len() - 1];
+    let (key, value) = match named_argument(arg) {
+        Some(named) => named,
+        None => {
+            let (skip, arrow_pos) = find_array_arrow(arg)?;
+            (&arg[..skip + arrow_pos + 2], arg[skip + arrow_pos + 2..].trim())
+        }
+    };
+    let (open, inner, close) = array_literal(value)?;
     let items = split_by_commas(inner);
     if items.len() <= 1 {
         return None;
     }
-    let key = &arg[..skip + arrow_pos + 2];
     let nested_pad = format!("{pad}{INDENT}");
-    let mut result = format!("{pad}{key} [\n");
+    let mut result = format!("{pad}{key} {open}\n");
     for item in &items {
         if nested_pad.len() + item.len() + 1 > MAX_LINE_LENGTH {
             if let Some(expanded) = expand_nested_array(item, &nested_pad) {
@@ -542,6 +548,6 @@ pub fn expand_nested_array(arg: &str, pad: &str) -> Option<String> {
         }
         result.push_str(&format!("{nested_pad}{item},\n"));
     }
-    result.push_str(&format!("{pad}],\n"));
+    result.push_str(&format!("{pad}{close},\n"));
     Some(result)
 }
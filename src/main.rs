@@ -1,5 +1,7 @@
 use clap::Parser;
-use phew::parser::{ast, lexer};
+use phew::config::{Config, ConfigError, UnclosedTags};
+use phew::parser::{ast, ignore, lexer};
+use std::path::Path;
 
 #[derive(Parser)]
 #[command(name = "phew")]
@@ -16,6 +18,9 @@ struct Cli {
 
     #[arg(long, help = "Show AST tree")]
     tree: bool,
+
+    #[arg(long, help = "Path to config file (default: nearest .phew.toml)")]
+    config: Option<String>,
 }
 
 fn print_tree(nodes: &[ast::Node], indent: usize) {
@@ -26,9 +31,11 @@ fn print_tree(nodes: &[ast::Node], indent: usize) {
                 name,
                 attributes,
                 children,
+                closed,
             } => {
+                let mark = if *closed { "" } else { " (unclosed)" };
                 if attributes.is_empty() {
-                    println!("{pad}<{name}>");
+                    println!("{pad}<{name}>{mark}");
                 } else {
                     let attrs: Vec<String> = attributes
                         .iter()
@@ -37,7 +44,7 @@ fn print_tree(nodes: &[ast::Node], indent: usize) {
                             None => a.name.clone(),
                         })
                         .collect();
-                    println!("{pad}<{name} {}>", attrs.join(" "));
+                    println!("{pad}<{name} {}>{mark}", attrs.join(" "));
                 }
                 print_tree(children, indent + 1);
             }
@@ -50,17 +57,50 @@ fn print_tree(nodes: &[ast::Node], indent: usize) {
             ast::Node::PhpBlock(s) => println!("{pad}PHP: <?php {s} ?>"),
             ast::Node::PhpEcho(s) => println!("{pad}PHP: <?= {s} ?>"),
             ast::Node::Doctype(s) => println!("{pad}DOCTYPE: {s}"),
-            ast::Node::Comment(s) => println!("{pad}COMMENT: {s}"),
+            ast::Node::Comment(s) => println!("{pad}COMMENT: {}", s.trim()),
+            ast::Node::Verbatim(s) => println!("{pad}VERBATIM: {s:?}"),
+        }
+    }
+}
+
+fn resolve_config(path: &str, cli: &Cli) -> Result<Config, ConfigError> {
+    if let Some(explicit) = &cli.config {
+        return Config::load(Path::new(explicit));
+    }
+    let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| Path::new(path).to_path_buf());
+    let dir = absolute.parent().unwrap_or(Path::new("."));
+    Config::discover(dir)
+}
+
+/// Reports implicitly closed elements per `unclosed_tags`; false when the file must not be formatted.
+fn check_unclosed(path: &str, nodes: &[ast::Node], config: &Config) -> bool {
+    let unclosed = ast::unclosed_elements(nodes);
+    if unclosed.is_empty() {
+        return true;
+    }
+    let list = unclosed
+        .iter()
+        .map(|name| format!("<{name}>"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match config.unclosed_tags {
+        UnclosedTags::Insert => eprintln!("Warning: {path}: inserted missing closing tags for {list}"),
+        UnclosedTags::Preserve => {}
+        UnclosedTags::Error => {
+            eprintln!("Error in {path}: missing closing tags for {list}");
+            return false;
         }
     }
+    true
 }
 
-fn process_file(path: &str, cli: &Cli) {
+/// Formats or dumps one file; false when it could not be processed.
+fn process_file(path: &str, cli: &Cli) -> bool {
     let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Error reading {path}: {e}");
-            return;
+            return false;
         }
     };
 
@@ -76,16 +116,32 @@ fn process_file(path: &str, cli: &Cli) {
         println!("=== {path} ===");
         print_tree(&nodes, 0);
     } else {
-        let nodes = ast::parse(tokens);
-        let formatted = phew::formatter::engine::format(&nodes);
+        let config = match resolve_config(path, cli) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error loading config for {path}: {e}");
+                return false;
+            }
+        };
+        let formatted = if ignore::ignores_file(&tokens) {
+            content.clone()
+        } else {
+            let nodes = ast::parse(tokens);
+            if !check_unclosed(path, &nodes, &config) {
+                return false;
+            }
+            phew::formatter::engine::format(&nodes, &config)
+        };
         if cli.write {
             if let Err(e) = std::fs::write(path, &formatted) {
                 eprintln!("Error writing {path}: {e}");
+                return false;
             }
         } else {
             print!("{formatted}");
         }
     }
+    true
 }
 
 fn main() {
@@ -108,8 +164,12 @@ fn main() {
         files.push(path.clone());
     }
 
+    let mut failed = false;
     for path in &files {
-        process_file(path, &cli);
+        failed |= !process_file(path, &cli);
+    }
+    if failed {
+        std::process::exit(1);
     }
 }
 
@@ -4,46 +4,113 @@ const VOID_ELEMENTS: &[&str] = &[
     "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
 ];
 
-fn is_void_element(name: &str) -> bool {
+const FOREIGN_ROOTS: &[&str] = &["svg", "math"];
+
+const FOREIGN_INTEGRATION_POINTS: &[&str] = &["foreignobject", "annotation-xml"];
+
+pub(crate) fn is_void_element(name: &str) -> bool {
     VOID_ELEMENTS.contains(&name.to_lowercase().as_str())
 }
 
-#[derive(Debug, PartialEq)]
+/// Reports whether the innermost open elements put the next tag in SVG/MathML content,
+/// where the HTML void list does not apply.
+fn in_foreign_content<'a>(open: impl Iterator<Item = &'a str>) -> bool {
+    open.fold(false, |foreign, name| {
+        let lower = name.to_lowercase();
+        if FOREIGN_ROOTS.contains(&lower.as_str()) {
+            true
+        } else if FOREIGN_INTEGRATION_POINTS.contains(&lower.as_str()) {
+            false
+        } else {
+            foreign
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Element {
         name: String,
         attributes: Vec<Attribute>,
         children: Vec<Node>,
+        /// The source has an end tag; void and self-closing elements count as closed.
+        closed: bool,
     },
     Text(String),
     PhpBlock(String),
     PhpEcho(String),
     Doctype(String),
     Comment(String),
+    Verbatim(String),
+}
+
+fn fragment_boundary(stack: &[(String, Vec<Attribute>, Vec<Node>)]) -> usize {
+    stack
+        .iter()
+        .rposition(|(n, _, _)| n.eq_ignore_ascii_case("template"))
+        .unwrap_or(0)
 }
 
 fn close_tag_unwind(close_name: &str, stack: &mut Vec<(String, Vec<Attribute>, Vec<Node>)>, current: &mut Vec<Node>) {
     let close_lower = close_name.to_lowercase();
-    if let Some(pos) = stack.iter().rposition(|(n, _, _)| n.to_lowercase() == close_lower) {
-        while stack.len() > pos + 1 {
+    let boundary = fragment_boundary(stack);
+    let found = stack[boundary..]
+        .iter()
+        .rposition(|(n, _, _)| n.to_lowercase() == close_lower);
+    if let Some(pos) = found.map(|p| p + boundary) {
+        while stack.len() > pos {
             if let Some((name, attributes, mut parent)) = stack.pop() {
                 parent.push(Node::Element {
                     name,
                     attributes,
                     children: std::mem::take(current),
+                    closed: stack.len() == pos,
                 });
                 *current = parent;
             }
         }
-        if let Some((name, attributes, mut parent)) = stack.pop() {
-            parent.push(Node::Element {
-                name,
-                attributes,
-                children: std::mem::take(current),
-            });
-            *current = parent;
+    }
+}
+
+/// Names of elements that were closed implicitly, by an outer end tag or the end of input.
+pub fn unclosed_elements(nodes: &[Node]) -> Vec<&str> {
+    let mut names = Vec::new();
+    for node in nodes {
+        if let Node::Element {
+            name, children, closed, ..
+        } = node
+        {
+            if !closed {
+                names.push(name.as_str());
+            }
+            names.extend(unclosed_elements(children));
+        }
+    }
+    names
+}
+
+/// Reports whether every non-void open tag is closed in nesting order.
+///
+/// Used before reparsing embedded markup, where a stray or foreign tag such as `<%= %>` would be mangled.
+pub fn is_well_formed(tokens: &[Token]) -> bool {
+    let mut open: Vec<String> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::OpenTag { name, .. } | Token::SelfClosing { name, .. }
+                if !name.starts_with(|c: char| c.is_ascii_alphabetic()) =>
+            {
+                return false;
+            }
+            Token::OpenTag { name, .. }
+                if !is_void_element(name) || in_foreign_content(open.iter().map(String::as_str)) =>
+            {
+                open.push(name.to_lowercase());
+            }
+            Token::CloseTag(name) if open.pop().as_deref() != Some(name.to_lowercase().as_str()) => return false,
+            _ => {}
         }
     }
+    open.is_empty()
 }
 
 pub fn parse(tokens: Vec<Token>) -> Vec<Node> {
@@ -53,11 +120,12 @@ pub fn parse(tokens: Vec<Token>) -> Vec<Node> {
     for token in tokens {
         match token {
             Token::OpenTag { name, attributes } => {
-                if is_void_element(&name) {
+                if is_void_element(&name) && !in_foreign_content(stack.iter().map(|(n, _, _)| n.as_str())) {
                     current.push(Node::Element {
                         name,
                         attributes,
                         children: Vec::new(),
+                        closed: true,
                     });
                 } else {
                     stack.push((name, attributes, std::mem::take(&mut current)));
@@ -69,6 +137,7 @@ pub fn parse(tokens: Vec<Token>) -> Vec<Node> {
                     name,
                     attributes,
                     children: Vec::new(),
+                    closed: true,
                 });
             }
             Token::Text(s) => current.push(Node::Text(s)),
@@ -76,6 +145,7 @@ pub fn parse(tokens: Vec<Token>) -> Vec<Node> {
             Token::PhpEcho(s) => current.push(Node::PhpEcho(s)),
             Token::Doctype(s) => current.push(Node::Doctype(s)),
             Token::Comment(s) => current.push(Node::Comment(s)),
+            Token::Verbatim(s) => current.push(Node::Verbatim(s)),
         }
     }
     while let Some((name, attributes, mut parent)) = stack.pop() {
@@ -83,6 +153,7 @@ pub fn parse(tokens: Vec<Token>) -> Vec<Node> {
             name,
             attributes,
             children: std::mem::take(&mut current),
+            closed: false,
         });
         current = parent;
     }
@@ -99,6 +170,7 @@ mod tests {
         Attribute {
             name: name.into(),
             value: value.map(Into::into),
+            line_break: false,
         }
     }
 
@@ -124,6 +196,7 @@ mod tests {
                 name: "div".into(),
                 attributes: vec![],
                 children: vec![Node::Text("hello".into())],
+                closed: true,
             }]
         );
     }
@@ -153,7 +226,9 @@ mod tests {
                     name: "span".into(),
                     attributes: vec![],
                     children: vec![Node::Text("x".into())],
+                    closed: true,
                 }],
+                closed: true,
             }]
         );
     }
@@ -175,6 +250,7 @@ mod tests {
                 name: "div".into(),
                 attributes: vec![attr("class", Some("item"))],
                 children: vec![Node::PhpEcho("$name".into())],
+                closed: true,
             }]
         );
     }
@@ -200,12 +276,64 @@ mod tests {
                     name: "p".into(),
                     attributes: vec![],
                     children: vec![Node::Text("hi".into())],
+                    closed: true,
                 },
                 Node::PhpBlock("endif;".into()),
             ]
         );
     }
 
+    #[test]
+    fn stray_close_tag_stays_inside_template() {
+        let tokens = vec![
+            Token::OpenTag {
+                name: "div".into(),
+                attributes: vec![],
+            },
+            Token::OpenTag {
+                name: "template".into(),
+                attributes: vec![],
+            },
+            Token::Text("x".into()),
+            Token::CloseTag("div".into()),
+            Token::CloseTag("template".into()),
+            Token::CloseTag("div".into()),
+        ];
+
+        assert_eq!(
+            parse(tokens),
+            vec![Node::Element {
+                name: "div".into(),
+                attributes: vec![],
+                children: vec![Node::Element {
+                    name: "template".into(),
+                    attributes: vec![],
+                    children: vec![Node::Text("x".into())],
+                    closed: true,
+                }],
+                closed: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn well_formed_detection() {
+        let open = |name: &str| Token::OpenTag {
+            name: name.into(),
+            attributes: vec![],
+        };
+        let close = |name: &str| Token::CloseTag(name.into());
+        assert!(is_well_formed(&[
+            open("ul"),
+            open("li"),
+            open("br"),
+            close("li"),
+            close("ul")
+        ]));
+        assert!(!is_well_formed(&[open("ul"), open("li"), close("ul")]));
+        assert!(!is_well_formed(&[open("%="), close("%=")]));
+    }
+
     #[test]
     fn self_closing_in_tree() {
         let tokens = vec![
@@ -229,8 +357,40 @@ mod tests {
                     name: "br".into(),
                     attributes: vec![],
                     children: vec![],
+                    closed: true,
                 }],
+                closed: true,
             }]
         );
     }
+
+    #[test]
+    fn void_names_not_void_in_foreign_content() {
+        let element = |name: &str, children: Vec<Node>| Node::Element {
+            name: name.into(),
+            attributes: vec![],
+            children,
+            closed: true,
+        };
+        let tokens = crate::parser::lexer::tokenize("<svg><source></source><foreignObject><br></foreignObject></svg>");
+        assert!(is_well_formed(&tokens));
+        assert_eq!(
+            parse(tokens),
+            vec![element(
+                "svg",
+                vec![
+                    element("source", vec![]),
+                    element("foreignObject", vec![element("br", vec![])]),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn implicitly_closed_elements_reported() {
+        let nodes = parse(crate::parser::lexer::tokenize(
+            "<div><ul><li>a</ul><br><p>b</div><section>",
+        ));
+        assert_eq!(unclosed_elements(&nodes), vec!["li", "p", "section"]);
+    }
 }
@@ -0,0 +1,195 @@
+use super::ast::{is_void_element, is_well_formed};
+use super::lexer::Token;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Directive {
+    /// `phew-ignore`: keep the next node as written.
+    Next,
+    /// `phew-ignore-start`: keep everything up to `phew-ignore-end` as written.
+    Start,
+    End,
+    /// `phew-ignore-file`: leave the whole file untouched.
+    File,
+}
+
+fn comment_text(code: &str) -> &str {
+    let code = code.trim();
+    if let Some(block) = code.strip_prefix("/*").and_then(|c| c.strip_suffix("*/")) {
+        return block.trim();
+    }
+    code.strip_prefix("//")
+        .or_else(|| code.strip_prefix('#').filter(|rest| !rest.starts_with('[')))
+        .map_or("", str::trim)
+}
+
+/// Reads a directive from `<!-- phew-ignore -->` or `<?php // phew-ignore ?>`.
+pub fn directive(token: &Token) -> Option<Directive> {
+    let text = match token {
+        Token::Comment(s) => s.trim(),
+        Token::PhpBlock(code) if !code.contains('\n') => comment_text(code),
+        _ => return None,
+    };
+    match text {
+        "phew-ignore" => Some(Directive::Next),
+        "phew-ignore-start" => Some(Directive::Start),
+        "phew-ignore-end" => Some(Directive::End),
+        "phew-ignore-file" => Some(Directive::File),
+        _ => None,
+    }
+}
+
+/// Reports whether the file carries a `phew-ignore-file` directive.
+pub fn ignores_file(tokens: &[Token]) -> bool {
+    tokens.iter().any(|token| directive(token) == Some(Directive::File))
+}
+
+fn is_blank_text(token: &Token) -> bool {
+    matches!(token, Token::Text(s) if s.trim().is_empty())
+}
+
+/// Index of the last token of the node that starts at `first`, following tags to their close.
+fn node_end(tokens: &[(Token, Range<usize>)], first: usize) -> usize {
+    let mut open: Vec<String> = Vec::new();
+    for (i, (token, _)) in tokens.iter().enumerate().skip(first) {
+        match token {
+            Token::OpenTag { name, .. } if !is_void_element(name) => open.push(name.to_lowercase()),
+            Token::CloseTag(name) => {
+                if let Some(pos) = open.iter().rposition(|n| *n == name.to_lowercase()) {
+                    open.truncate(pos);
+                }
+            }
+            _ => {}
+        }
+        if open.is_empty() {
+            return i;
+        }
+    }
+    tokens.len() - 1
+}
+
+/// The source slice of `span`, widened to the indentation before it when it starts a line.
+fn verbatim(input: &str, span: Range<usize>) -> Token {
+    let line_start = input[..span.start].rfind('\n').map_or(0, |pos| pos + 1);
+    let indent = &input[line_start..span.start];
+    let start = if indent.trim().is_empty() {
+        line_start
+    } else {
+        span.start
+    };
+    Token::Verbatim(input[start..span.end].to_string())
+}
+
+/// Reports whether the tokens from `first` to `last` open and close their tags in balance, so
+/// a verbatim slice of them cannot take an end tag from the surrounding markup.
+fn is_balanced(tokens: &[(Token, Range<usize>)], first: usize, last: usize) -> bool {
+    let slice: Vec<Token> = tokens[first..=last].iter().map(|(token, _)| token.clone()).collect();
+    is_well_formed(&slice)
+}
+
+/// Replaces the nodes and regions marked by ignore directives with verbatim source slices;
+/// a directive whose node or region would cross an element boundary is dropped.
+pub fn apply_directives(input: &str, tokens: Vec<(Token, Range<usize>)>) -> Vec<Token> {
+    if !tokens.iter().any(|(token, _)| directive(token).is_some()) {
+        return tokens.into_iter().map(|(token, _)| token).collect();
+    }
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        match directive(&tokens[i].0) {
+            Some(Directive::Start) => {
+                let end = (i + 1..tokens.len())
+                    .find(|&j| directive(&tokens[j].0) == Some(Directive::End))
+                    .unwrap_or(tokens.len() - 1);
+                if is_balanced(&tokens, i, end) {
+                    out.push(verbatim(input, tokens[i].1.start..tokens[end].1.end));
+                    i = end + 1;
+                    continue;
+                }
+            }
+            Some(Directive::Next) => {
+                let next = (i + 1..tokens.len()).find(|&j| !is_blank_text(&tokens[j].0));
+                let node = next
+                    .filter(|&first| !matches!(tokens[first].0, Token::CloseTag(_)))
+                    .map(|first| (first, node_end(&tokens, first)))
+                    .filter(|&(first, last)| is_balanced(&tokens, first, last));
+                if let Some((first, last)) = node {
+                    out.extend(tokens[i..first].iter().map(|(token, _)| token.clone()));
+                    out.push(verbatim(input, tokens[first].1.start..tokens[last].1.end));
+                    i = last + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(tokens[i].0.clone());
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::lexer::tokenize;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn directives_recognized_in_html_and_php_comments() {
+        assert_eq!(
+            directive(&Token::Comment(" phew-ignore ".into())),
+            Some(Directive::Next)
+        );
+        assert_eq!(
+            directive(&Token::PhpBlock("// phew-ignore".into())),
+            Some(Directive::Next)
+        );
+        assert_eq!(
+            directive(&Token::PhpBlock("/* phew-ignore-start */".into())),
+            Some(Directive::Start)
+        );
+        assert_eq!(
+            directive(&Token::PhpBlock("# phew-ignore-file".into())),
+            Some(Directive::File)
+        );
+        assert_eq!(directive(&Token::Comment("phew-ignore later".into())), None);
+        assert_eq!(directive(&Token::PhpBlock("$a = 1; // phew-ignore".into())), None);
+    }
+
+    #[test]
+    fn next_node_kept_with_nested_tags() {
+        let tokens = tokenize("<!-- phew-ignore -->\n<table><tr><td>a</td>\n   <td>b</td></tr></table>\n<p>x</p>");
+        assert_eq!(
+            tokens[2],
+            Token::Verbatim("<table><tr><td>a</td>\n   <td>b</td></tr></table>".into())
+        );
+        assert!(matches!(tokens[4], Token::OpenTag { ref name, .. } if name == "p"));
+    }
+
+    #[test]
+    fn region_kept_up_to_end_marker() {
+        let source = "<div>\n<!-- phew-ignore-start -->\n  <b>a</b>   <i>b</i>\n<?php // phew-ignore-end ?>\n</div>";
+        let tokens = tokenize(source);
+        assert_eq!(
+            tokens[2],
+            Token::Verbatim("<!-- phew-ignore-start -->\n  <b>a</b>   <i>b</i>\n<?php // phew-ignore-end ?>".into())
+        );
+        assert_eq!(tokens[4], Token::CloseTag("div".into()));
+    }
+
+    #[test]
+    fn directive_before_close_tag_dropped() {
+        let tokens = tokenize("<div><p>a</p><!-- phew-ignore --></div><i>b</i>");
+        assert_eq!(tokens[4], Token::Comment(" phew-ignore ".into()));
+        assert_eq!(tokens[5], Token::CloseTag("div".into()));
+        assert!(!tokens.iter().any(|token| matches!(token, Token::Verbatim(_))));
+    }
+
+    #[test]
+    fn region_crossing_element_boundary_dropped() {
+        let source = "<section><div><!-- phew-ignore-start --><b>x</b></div><!-- phew-ignore-end --><p>y</p></section>";
+        let tokens = tokenize(source);
+        assert!(!tokens.iter().any(|token| matches!(token, Token::Verbatim(_))));
+        assert_eq!(tokens[6], Token::CloseTag("div".into()));
+    }
+}
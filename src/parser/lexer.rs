@@ -1,35 +1,82 @@
-use std::iter::Peekable;
+use super::ignore::apply_directives;
+use std::ops::Range;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Attribute {
     pub name: String,
     pub value: Option<String>,
+    /// The attribute starts on a new line in the source tag.
+    pub line_break: bool,
 }
 
 const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea"];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Text(String),
-    OpenTag { name: String, attributes: Vec<Attribute> },
+    OpenTag {
+        name: String,
+        attributes: Vec<Attribute>,
+    },
     CloseTag(String),
-    SelfClosing { name: String, attributes: Vec<Attribute> },
+    SelfClosing {
+        name: String,
+        attributes: Vec<Attribute>,
+    },
     PhpBlock(String),
     PhpEcho(String),
     Doctype(String),
     Comment(String),
+    /// Source copied byte-for-byte because of a `phew-ignore` directive.
+    Verbatim(String),
 }
 
-fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+/// Character cursor that tracks its byte offset in the source, so tokens can carry spans.
+#[derive(Clone)]
+struct Cursor<'a> {
+    source: &'a str,
+    offset: usize,
+    current: Option<char>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            offset: 0,
+            current: source.chars().next(),
+        }
+    }
+
+    fn peek(&self) -> Option<&char> {
+        self.current.as_ref()
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.current?;
+        self.offset += c.len_utf8();
+        self.current = self.source[self.offset..].chars().next();
+        Some(c)
+    }
+}
+
+fn skip_whitespace(chars: &mut Cursor<'_>) -> bool {
+    let mut newline = false;
     while let Some(&c) = chars.peek() {
         if !c.is_whitespace() {
             break;
         }
+        newline |= c == '\n';
         chars.next();
     }
+    newline
 }
 
-fn consume_attr_name(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+fn consume_attr_name(chars: &mut Cursor<'_>) -> String {
     let mut name = String::new();
     while let Some(&c) = chars.peek() {
         if c == '=' || c.is_whitespace() {
@@ -41,7 +88,7 @@ fn consume_attr_name(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> St
     name
 }
 
-fn consume_attr_value(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+fn consume_attr_value(chars: &mut Cursor<'_>) -> String {
     let mut value = String::new();
 
     if let Some(&quote) = chars.peek()
@@ -70,7 +117,7 @@ fn consume_attr_value(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> S
     value
 }
 
-fn try_consume_php_attr(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Attribute> {
+fn try_consume_php_attr(chars: &mut Cursor<'_>) -> Option<Attribute> {
     if chars.peek() != Some(&'<') {
         return None;
     }
@@ -94,18 +141,19 @@ fn try_consume_php_attr(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Att
     Some(Attribute {
         name: php_buf,
         value: None,
+        line_break: false,
     })
 }
 
 fn parse_attributes(raw: &str) -> Vec<Attribute> {
     let mut attrs = Vec::new();
-    let mut chars = raw.chars().peekable();
+    let mut chars = Cursor::new(raw);
 
     loop {
-        skip_whitespace(&mut chars);
+        let line_break = skip_whitespace(&mut chars);
 
         if let Some(attr) = try_consume_php_attr(&mut chars) {
-            attrs.push(attr);
+            attrs.push(Attribute { line_break, ..attr });
             continue;
         }
 
@@ -123,9 +171,14 @@ fn parse_attributes(raw: &str) -> Vec<Attribute> {
             attrs.push(Attribute {
                 name,
                 value: Some(value),
+                line_break,
             });
         } else {
-            attrs.push(Attribute { name, value: None });
+            attrs.push(Attribute {
+                name,
+                value: None,
+                line_break,
+            });
         }
     }
 
@@ -147,7 +200,7 @@ fn parse_tag(tag_content: &str) -> Token {
     };
 
     let (name, rest) = match body.find(|c: char| c.is_whitespace()) {
-        Some(pos) => (&body[..pos], body[pos..].trim_start()),
+        Some(pos) => (&body[..pos], &body[pos..]),
         None => (body, ""),
     };
 
@@ -166,7 +219,7 @@ fn parse_tag(tag_content: &str) -> Token {
     }
 }
 
-fn consume_php_block(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+fn consume_php_block(chars: &mut Cursor<'_>) -> String {
     let mut content = String::new();
     let mut in_string: Option<char> = None;
     while let Some(&c) = chars.peek() {
@@ -209,7 +262,7 @@ fn consume_php_block(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> St
     }
 }
 
-fn consume_raw_text(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, tag_name: &str) -> (String, bool) {
+fn consume_raw_text(chars: &mut Cursor<'_>, tag_name: &str) -> (String, bool) {
     let mut content = String::new();
     let close_pattern = format!("</{}", tag_name);
     let close_upper = close_pattern.to_uppercase();
@@ -237,7 +290,7 @@ fn consume_raw_text(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, tag_na
     (content, false)
 }
 
-fn consume_php_tag_prefix(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> bool {
+fn consume_php_tag_prefix(chars: &mut Cursor<'_>) -> bool {
     if !matches!(chars.peek(), Some(&'h') | Some(&'H')) {
         return false;
     }
@@ -249,7 +302,7 @@ fn consume_php_tag_prefix(chars: &mut std::iter::Peekable<std::str::Chars<'_>>)
     true
 }
 
-fn try_consume_php(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<Token> {
+fn try_consume_php(chars: &mut Cursor<'_>) -> Option<Token> {
     let mut look = chars.clone();
 
     if look.next() != Some('?') {
@@ -289,7 +342,7 @@ fn try_consume_php(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Opti
     }
 }
 
-fn try_consume_comment(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Token> {
+fn try_consume_comment(chars: &mut Cursor<'_>) -> Option<Token> {
     let mut look = chars.clone();
     look.next();
     let next_two: String = look.take(2).collect();
@@ -319,10 +372,10 @@ fn try_consume_comment(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Toke
             Some(c) => comment.push(c),
         }
     }
-    Some(Token::Comment(comment.trim().to_string()))
+    Some(Token::Comment(comment))
 }
 
-fn try_consume_doctype(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Token> {
+fn try_consume_doctype(chars: &mut Cursor<'_>) -> Option<Token> {
     let mut look = chars.clone();
     look.next();
     let rest: String = look.take(7).collect();
@@ -344,7 +397,7 @@ fn try_consume_doctype(chars: &mut Peekable<std::str::Chars<'_>>) -> Option<Toke
     Some(Token::Doctype(buf.trim().to_string()))
 }
 
-fn consume_php_in_tag(chars: &mut Peekable<std::str::Chars<'_>>, buf: &mut String) {
+fn consume_php_in_tag(chars: &mut Cursor<'_>, buf: &mut String) {
     buf.push('<');
     buf.push('?');
     chars.next();
@@ -359,7 +412,7 @@ fn consume_php_in_tag(chars: &mut Peekable<std::str::Chars<'_>>, buf: &mut Strin
     }
 }
 
-fn consume_tag_body(chars: &mut Peekable<std::str::Chars<'_>>) -> String {
+fn consume_tag_body(chars: &mut Cursor<'_>) -> String {
     let mut buf = String::new();
     let mut in_quote: Option<char> = None;
     while let Some(&c) = chars.peek() {
@@ -400,72 +453,81 @@ fn consume_tag_body(chars: &mut Peekable<std::str::Chars<'_>>) -> String {
     buf
 }
 
-fn emit_tag_token(tag_buf: &str, chars: &mut Peekable<std::str::Chars<'_>>, tokens: &mut Vec<Token>) {
+fn emit_tag_token(tag_buf: &str, start: usize, chars: &mut Cursor<'_>, tokens: &mut Vec<(Token, Range<usize>)>) {
     let tag = parse_tag(tag_buf);
+    let tag_end = chars.offset;
     if let Token::OpenTag { ref name, .. } = tag {
         if RAW_TEXT_ELEMENTS.contains(&name.to_lowercase().as_str()) {
             let tag_name = name.clone();
-            tokens.push(tag);
+            tokens.push((tag, start..tag_end));
             let (raw_content, found_close) = consume_raw_text(chars, &tag_name);
+            let raw_end = tag_end + raw_content.len();
             if !raw_content.is_empty() {
-                tokens.push(Token::Text(raw_content));
+                tokens.push((Token::Text(raw_content), tag_end..raw_end));
             }
             if found_close {
-                tokens.push(Token::CloseTag(tag_name));
+                tokens.push((Token::CloseTag(tag_name), raw_end..chars.offset));
             }
             return;
         }
     }
-    tokens.push(tag);
+    tokens.push((tag, start..tag_end));
 }
 
-pub fn tokenize(input: &str) -> Vec<Token> {
+fn flush_text(text_buf: &mut String, end: usize, tokens: &mut Vec<(Token, Range<usize>)>) {
+    if !text_buf.is_empty() {
+        let start = end - text_buf.len();
+        tokens.push((Token::Text(std::mem::take(text_buf)), start..end));
+    }
+}
+
+/// Splits the source into tokens paired with their byte ranges.
+fn tokenize_spanned(input: &str) -> Vec<(Token, Range<usize>)> {
     let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+    let mut chars = Cursor::new(input);
     let mut text_buf = String::new();
 
     while let Some(&ch) = chars.peek() {
         if ch == '<' {
+            let start = chars.offset;
             chars.next();
 
             if let Some(php_token) = try_consume_php(&mut chars) {
-                if !text_buf.is_empty() {
-                    tokens.push(Token::Text(std::mem::take(&mut text_buf)));
-                }
-                tokens.push(php_token);
+                flush_text(&mut text_buf, start, &mut tokens);
+                tokens.push((php_token, start..chars.offset));
                 continue;
             }
 
-            if !text_buf.is_empty() {
-                tokens.push(Token::Text(std::mem::take(&mut text_buf)));
-            }
+            flush_text(&mut text_buf, start, &mut tokens);
 
             if chars.peek() == Some(&'!') {
                 if let Some(t) = try_consume_comment(&mut chars) {
-                    tokens.push(t);
+                    tokens.push((t, start..chars.offset));
                     continue;
                 }
                 if let Some(t) = try_consume_doctype(&mut chars) {
-                    tokens.push(t);
+                    tokens.push((t, start..chars.offset));
                     continue;
                 }
             }
 
             let tag_buf = consume_tag_body(&mut chars);
-            emit_tag_token(&tag_buf, &mut chars, &mut tokens);
+            emit_tag_token(&tag_buf, start, &mut chars, &mut tokens);
         } else {
             text_buf.push(ch);
             chars.next();
         }
     }
 
-    if !text_buf.is_empty() {
-        tokens.push(Token::Text(text_buf));
-    }
+    flush_text(&mut text_buf, chars.offset, &mut tokens);
 
     tokens
 }
 
+pub fn tokenize(input: &str) -> Vec<Token> {
+    apply_directives(input, tokenize_spanned(input))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,6 +541,7 @@ mod tests {
                 .map(|(n, v)| Attribute {
                     name: n.into(),
                     value: v.map(Into::into),
+                    line_break: false,
                 })
                 .collect(),
         }
@@ -496,6 +559,7 @@ mod tests {
                 .map(|(n, v)| Attribute {
                     name: n.into(),
                     value: v.map(Into::into),
+                    line_break: false,
                 })
                 .collect(),
         }
@@ -617,6 +681,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_conversion)]
     fn script_raw_text() {
         assert_eq!(
             tokenize("<script>if (a < b) { alert(1); }</script>"),
@@ -629,6 +694,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_conversion)]
     fn style_raw_text() {
         assert_eq!(
             tokenize("<style>.a > .b { color: red; }</style>"),
@@ -641,6 +707,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_conversion)]
     fn script_with_attributes() {
         assert_eq!(
             tokenize(r#"<script type="text/javascript">var x = 1;</script>"#),
@@ -669,7 +736,7 @@ mod tests {
     fn comment_token() {
         assert_eq!(
             tokenize("<!-- This is a comment -->"),
-            vec![Token::Comment("This is a comment".into())]
+            vec![Token::Comment(" This is a comment ".into())]
         );
     }
 
@@ -682,7 +749,7 @@ mod tests {
                 text("\n"),
                 open("html", vec![]),
                 text("\n"),
-                Token::Comment("comment".into()),
+                Token::Comment(" comment ".into()),
                 text("\n"),
                 open("body", vec![]),
                 close("body"),
@@ -691,4 +758,14 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn attribute_line_breaks_recorded() {
+        let tokens = tokenize("<div\n    class=\"a\" id=\"b\"\n    <?= $x ?>>");
+        let Token::OpenTag { attributes, .. } = &tokens[0] else {
+            panic!("expected open tag");
+        };
+        let breaks: Vec<bool> = attributes.iter().map(|a| a.line_break).collect();
+        assert_eq!(breaks, vec![true, false, true]);
+    }
 }
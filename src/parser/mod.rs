@@ -1,3 +1,4 @@
 pub mod ast;
+pub mod ignore;
 pub mod lexer;
 pub mod tree;
@@ -0,0 +1,26 @@
+<?php
+
+use yii\helpers\Html;
+
+/**
+ * @var int $total
+ * @var array $items
+ */
+
+$count = count($items) - 1;
+$ratio = $total > 0 ? $count / $total : -1;
+$label = 'Items: ' . $count . ' of ' . (string) $total;
+$visible = !empty($items) && $count >= 0 || $total === null;
+$limit = $options['limit'] ?? 10;
+
+?>
+<div class="summary">
+    <?php if ($count % 2 == 0 && !$hidden): ?>
+        <span><?= $count * 100 / max($total, 1) ?>%</span>
+    <?php endif; ?>
+    <p><?= $user?->profile?->name ?? 'Guest' ?></p>
+    <p><?= Html::encode($model->title . ' #' . $model->id) ?></p>
+    <?php foreach ($items as $key => &$item): ?>
+        <?php $item['pos'] = $key + 1; ?>
+    <?php endforeach; ?>
+</div>
@@ -0,0 +1,9 @@
+<?php if ($model->isNew === true): ?>
+    <p><?= Html::encode($model->Title ?? null) ?></p>
+<?php elseif (Count($items) > 0): ?>
+    <?php foreach ($items as $item): ?>
+        <span><?= (int) $item['Price'] ?></span>
+    <?php endforeach; ?>
+<?php else: ?>
+    <?= Html::tag('p', 'NULL is kept in strings', ['data-FALSE' => false]) ?>
+<?php endif; ?>
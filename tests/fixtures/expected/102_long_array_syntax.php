@@ -0,0 +1,32 @@
+<?php $options = array('class' => 'form-control', 'placeholder' => 'Search'); ?>
+<div class="toolbar">
+    <?= Html::a(
+        'Very long link text that goes on',
+        array(
+            'site/view',
+            'id' => $model->id,
+        ),
+        array(
+            'class' => 'btn btn-primary btn-lg',
+            'data-method' => 'post',
+            'data-confirm' => 'Are you sure?',
+        ),
+    ) ?>
+    <?= GridView::widget(array(
+        'dataProvider' => $dataProvider,
+        'columns' => array(
+            'id',
+            'title',
+            'created_at',
+            'updated_at',
+            'status',
+            'author',
+            'category',
+            'tags',
+            'views',
+            'published_at',
+        ),
+    )) ?>
+    <?= $form->field($model, 'title')
+        ->textInput(array('maxlength' => true, 'class' => 'form-control form-control-lg', 'autofocus' => true)) ?>
+</div>
@@ -0,0 +1,10 @@
+<div class="card">
+    <h1><?= Html::encode($this->title) ?></h1>
+    <?= $form->field($model, 'name') ?>
+    <?= $model->description ?>
+    <?= Html::encode($model->title) // page title ?>
+    <?= $model->author # byline ?>
+    <?= Html::a($model->category->name, ['category/view', 'id' => $model->category_id]) ?>
+    <?php echo 'a';
+    echo 'b'; ?>
+</div>
@@ -0,0 +1,12 @@
+<div>
+    <?= $model->title // heading
+        . ' ' . $model->subtitle ?>
+    <?= Html::a(
+        'Edit', # label
+        ['update', 'id' => $model->id]
+    ) ?>
+    <?= $a /* note */ . $b ?>
+    <?= $x // first
+        . $y // last ?>
+    <?= '// not a comment' . $z ?>
+</div>
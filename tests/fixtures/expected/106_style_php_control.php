@@ -0,0 +1,20 @@
+<style>
+    .a {
+        color: red;
+    }
+    <?php if ($dark): ?>
+    .b {
+        color: <?= $fg ?>;
+        background: #000;
+    }
+    <?php else: ?>
+    a:hover {
+        color: blue;
+    }
+    <?php endif; ?>
+    .c {
+        <?php if ($wide): ?>
+        width: 100%;
+        <?php endif; ?>
+    }
+</style>
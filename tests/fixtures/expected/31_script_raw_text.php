@@ -1,6 +1,6 @@
 <div class="container">
     <script type="text/javascript">
- $(document).ready(function() { if (a < b && c > d) { $('#btn').click(function() { alert('clicked'); }); } });
+        $(document).ready(function() { if (a < b && c > d) { $('#btn').click(function() { alert('clicked'); }); } });
     </script>
     <p>Content after script</p>
 </div>
@@ -1,6 +1,12 @@
 <div class="page">
     <style>
- .container > .item { color: red; font-size: 14px; } .container > .item:hover { color: blue; }
+        .container > .item {
+            color: red;
+            font-size: 14px;
+        }
+        .container > .item:hover {
+            color: blue;
+        }
     </style>
     <p>Content after style</p>
 </div>
@@ -0,0 +1,43 @@
+<?php
+
+use yii\web\View;
+
+$url = Url::to(['site/search']);
+
+$this->registerJs(<<<JS
+    $('#search').on('input', function() {
+        if ($(this).val().length > 2) {
+            $.get('{$url}', { q: $(this).val() }, function(data) {
+                $('#results').html(data);
+            });
+        }
+    });
+JS, View::POS_READY);
+
+$this->registerCss(<<<'CSS'
+    .results {
+        margin-top: 10px;
+        padding: 0;
+    }
+    .results li {
+        border-bottom: 1px solid #eee;
+    }
+CSS);
+
+if ($model->isNewRecord) {
+    $this->registerJs(<<<JS
+          $('#form').trigger('reset');
+          if (window.init) {
+              window.init();
+          }
+      JS);
+}
+
+$this->registerJs(<<<EOT
+    var keep   =   'as is';
+EOT);
+
+?>
+<div class="content">
+    <ul class="results"></ul>
+</div>
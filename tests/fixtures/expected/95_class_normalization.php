@@ -0,0 +1,11 @@
+<div class="btn-group pull-right">
+    <a href="#" class="btn btn-primary pull-right">Save</a>
+    <span class="badge <?= $active ? 'bg-success' : 'bg-secondary' ?>  ms-2"><?= $count ?></span>
+    <div
+        class="d-flex flex-column flex-md-row justify-content-between align-items-start align-items-md-center gap-2
+            gap-md-3 px-3 py-2 mb-3 border-bottom bg-light shadow-sm rounded-top"
+        id="toolbar"
+    >
+        <p>toolbar</p>
+    </div>
+</div>
@@ -0,0 +1,30 @@
+<a href="<?= Url::to(['site/index']) ?>" class="logo">
+    <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24" width="24" height="24">
+        <defs>
+            <linearGradient id="logo-gradient" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="24" y2="24">
+                <stop offset="0" stop-color="#4f46e5" />
+                <stop offset="1" stop-color="<?= $accent ?>" />
+            </linearGradient>
+            <clipPath id="logo-clip">
+                <rect width="24" height="24" rx="4" />
+            </clipPath>
+        </defs>
+        <g clip-path="url(#logo-clip)">
+            <path
+                fill="url(#logo-gradient)"
+                d="M12 2C6.48 2 2 6.48 2 12s4.48 10 10 10 10-4.48 10-10S17.52 2 12 2zm0 18c-4.41 0-8-3.59-8-8s3.59-8 8-8 8 3.59 8 8-3.59 8-8 8z"
+             />
+            <foreignObject x="0" y="0" width="24" height="24">
+                <div class="label">
+                    <br />
+                    <?= Html::encode($name) ?>
+                </div>
+            </foreignObject>
+        </g>
+    </svg>
+</a>
+<math>
+    <mi>x</mi>
+    <mo>=</mo>
+    <mn>2</mn>
+</math>
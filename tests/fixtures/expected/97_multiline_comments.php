@@ -0,0 +1,24 @@
+<!DOCTYPE html>
+<html>
+    <head>
+        <!--[if lt IE 9]>
+    <script src="/js/html5shiv.js"></script>
+<![endif]-->
+        <!--[if !IE]><!-->
+        <link rel="stylesheet" href="/css/modern.css" />
+        <!--<![endif]-->
+    </head>
+    <body>
+        <div class="layout">
+            <aside>
+                <!--
+                    Sidebar widgets:
+                      - menu
+                      - search
+                -->
+                <?= $this->render('_sidebar') ?>
+            </aside>
+            <!-- Footer -->
+        </div>
+    </body>
+</html>
@@ -0,0 +1,21 @@
+<?php
+/**
+ * @var yii\web\View $this
+ */
+?>
+<div class="report">
+    <!-- phew-ignore -->
+<table class="matrix">
+  <tr><td>  1</td><td>  0</td></tr>
+  <tr><td>  0</td><td>  1</td></tr>
+</table>
+    <p>Formatted</p>
+    <?php // phew-ignore ?>
+<?php $grid = [ 'a'=>1,
+                'bb'=>2 ]; ?>
+<!-- phew-ignore-start -->
+<pre-rendered><b>x</b>   <i>y</i></pre-rendered>
+<span   class="raw">  kept  </span>
+<!-- phew-ignore-end -->
+    <span>after</span>
+</div>
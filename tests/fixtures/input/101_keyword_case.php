@@ -0,0 +1,9 @@
+<?PHP IF ($model->isNew === TRUE): ?>
+    <p><?= Html::encode($model->Title ?? NULL) ?></p>
+<?php ELSEIF (Count($items) > 0): ?>
+    <?php FOREACH ($items AS $item): ?>
+        <span><?= (INT) $item['Price'] ?></span>
+    <?php ENDFOREACH; ?>
+<?php ELSE: ?>
+    <?= Html::tag('p', 'NULL is kept in strings', ['data-FALSE' => FALSE]) ?>
+<?php ENDIF; ?>
@@ -0,0 +1,10 @@
+<div class="card">
+    <h1><?= Html::encode($this->title); ?></h1>
+    <?php echo $form->field($model, 'name'); ?>
+    <?php print $model->description; ?>
+    <?php echo Html::encode($model->title); // page title ?>
+    <?= $model->author; # byline ?>
+    <?php echo Html::a($model->category->name,
+        ['category/view', 'id' => $model->category_id]); ?>
+    <?php echo 'a'; echo 'b'; ?>
+</div>
@@ -0,0 +1,14 @@
+<div>
+    <?= $model->title // heading
+        . ' ' . $model->subtitle ?>
+    <?= Html::a(
+        'Edit', # label
+        ['update', 'id' => $model->id]
+    ) ?>
+    <?= $a /* note */
+        . $b ?>
+    <?php echo $x // first
+        . $y; // last ?>
+    <?= '// not a comment'
+        . $z ?>
+</div>
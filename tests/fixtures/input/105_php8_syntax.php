@@ -0,0 +1,26 @@
+<?php
+#[Deprecated]
+function render(Status $status): string { return $status->label(); }
+$handler = strlen(...);
+$cb = static fn($x) => $x * 2;
+$items = [...$defaults, ...$opts];
+?>
+<div class="article">
+    <?= $model?->author?->name ?>
+    <?= Html::a(text: $t, url: $u) ?>
+    <?= Html::a(text:$t, url:$u, options:['class' => 'btn']) ?>
+    <?= Status::Active->label() ?>
+    <?= $model?->getAuthor()?->getProfile()?->getDisplayName()?->toUpperCaseWithSomeVeryLongMethodName()?->trim() ?>
+    <?= Html::a(text: 'Very long link text that goes on and on', url: ['site/view', 'id' => $model->id], options: ['class' => 'btn']) ?>
+    <?= array_map(strtoupper(...), $names) ?>
+    <?= $flag ? 'a' : 'b' ?>
+    <?= match($x) { 1 => 'a', default => 'b' } ?>
+    <?= implode(', ', array_map(static fn(Status $s): string => $s->label(), Status::cases())) ?>
+    <?= $model?->getAuthor()?->getProfile()?->getDisplayName()?->toUpperCaseWithSomeVeryLongMethodName()?->trimWhitespaceAround() ?>
+    <?= Status::Active->label()->withSomeVeryLongMethodNameThatGoesOn()->andAnotherLongMethodName()->andOneMoreMethod() ?>
+    <?= $model?->isPublished() ? Html::tag('span', 'Published article', ['class' => 'badge']) : Html::tag('span', 'Draft') ?>
+    <?= Html::a(text: 'Very long link text that goes on and on', url: ['site/view', 'id' => $model->id, 'slug' => $model->slug, 'lang' => $lang]) ?>
+    <?= ListView::widget(dataProvider: $dataProvider, itemView: '_item', options: ['class' => 'list-view', 'id' => 'articles-list', 'data-pjax' => 1]) ?>
+    <?= $form->field($model, 'title', options: ['class' => 'form-group'])->textInput(['maxlength' => true, 'placeholder' => 'Title', 'autofocus' => true]) ?>
+    <?= Html::a(text: 'Very long link text', url: ['site/view', 'id' => $model->id, 'slug' => $model->slug, 'lang' => $lang, 'page' => $page, 'sort' => $sort]) ?>
+</div>
@@ -0,0 +1,13 @@
+<div class="banner">
+<style>
+/* banner colors */
+.banner{background:<?= $color ?>;padding:10px 20px}
+.banner a,.banner span{color:#fff}
+
+
+@media (max-width: 768px){
+.banner{padding:5px}
+}
+</style>
+<p>Promo</p>
+</div>
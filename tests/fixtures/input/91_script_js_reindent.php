@@ -0,0 +1,19 @@
+<div class="map">
+<script>
+      var markers = <?= json_encode($markers) ?>;
+  var pattern = /[{(]+/g;
+          $(function() {
+markers.forEach(function(m) {
+        if (m.visible) {
+    L.marker([m.lat, m.lng])
+  .bindPopup(`<b>${m.title}</b>
+    {raw}`)
+        .addTo(map);
+        }
+});
+  });
+</script>
+<script type="text/x-custom">
+   keep   this { as is
+</script>
+</div>
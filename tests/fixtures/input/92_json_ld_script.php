@@ -0,0 +1,5 @@
+<head>
+<script type="application/ld+json">{"@context":"https://schema.org","@type":"Organization","name":"Acme","sameAs":["https://x.com/acme","https://github.com/acme"]}</script>
+<script type="application/json">{"user": <?= json_encode($user) ?>}</script>
+</head>
+<div data-options='{"autoplay":true,"delay":300}'></div>
@@ -0,0 +1,14 @@
+<div id="app">
+<script type="text/x-template" id="item-template">
+<li class="item">
+<span>{{ item.title }}</span>
+<?php if ($showPrice): ?>
+<em><?= $currency ?></em>
+<?php endif; ?>
+</li>
+</script>
+<template id="row">
+<tr><td class="name"></td>
+<td class="price"><?= $defaultPrice ?></td></tr>
+</template>
+</div>
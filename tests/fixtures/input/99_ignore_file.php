@@ -0,0 +1,3 @@
+<?php // phew-ignore-file ?>
+<div><p>   generated   </p>
+      <span>as is</span></div>